@@ -6,7 +6,6 @@ use iced::{
 use std::{
     path::PathBuf,
     sync::{Arc, LazyLock},
-    usize,
 };
 
 use fuzzy_matcher::skim::SkimMatcherV2;
@@ -18,6 +17,7 @@ use iced::{
         text,
     },
 };
+use tokio::sync::watch;
 
 use crate::{
     preferences::{
@@ -25,13 +25,19 @@ use crate::{
         keybindings::{Action, Keystrokes},
         theme::{ContainerClass, CustomTheme, TextClass},
     },
-    providers::{ProviderKind, ScanRequest, ScannerState},
+    providers::{
+        ProviderKind, ScanRequest, ScannerState,
+        app::mime_apps,
+        mime,
+        terminal::resolve_terminal,
+        usage::{UsageHistory, UsageStore},
+    },
     ui::{
         self,
         entry::{EntryIcon, EntryRegistry, FONT_ITALIC, section},
         icon::{
             CLIPBOARD_ACTIVE, CLIPBOARD_INACTIVE, CUBE_ACTIVE, CUBE_INACTIVE, FOLDER_ACTIVE,
-            FOLDER_INACTIVE, MAGNIFIER,
+            FOLDER_INACTIVE, MAGNIFIER, TERMINAL_ACTIVE, TERMINAL_INACTIVE,
         },
         prompt::Prompt,
     },
@@ -55,9 +61,24 @@ pub struct Lucien {
     last_viewport: Option<Viewport>,
     search_handle: Option<iced::task::Handle>,
     path: PathBuf,
+    display_scale: f32,
+    /// Publishes `icon_scale()` to the running scan.
+    icon_scale_sender: watch::Sender<u32>,
+    usage_store: Option<UsageStore>,
+    usage_history: UsageHistory,
+    show_hidden_entries: bool,
+    recursive_search: bool,
+    /// The prompt as of the last debounce, which recursive file searches look for.
+    search_query: String,
+    /// The file whose applications are listed, while choosing what opens it.
+    open_with: Option<PathBuf>,
+    /// Set when the listing options change, so the directory listed again
+    /// stays filtered by the prompt.
+    keeps_prompt: bool,
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Message {
     ScanEvent(ScannerState),
     PromptChange(String),
@@ -66,16 +87,26 @@ pub enum Message {
     TriggerActionByKeybinding(Keystrokes),
     ScrollableViewport(Viewport),
     SaveIntoDisk(Result<PathBuf, Arc<tokio::io::Error>>),
-    IconResolved { id: String, handle: image::Handle },
+    IconsResolved(Vec<(String, image::Handle)>),
     HoveredEntry(usize),
     HoveredExit(usize),
     PreferencesLoaded(Result<Preferences, Arc<tokio::io::Error>>),
     ChangePath(PathBuf),
+    OpenFile(PathBuf),
+    FileOpened(Result<(), Arc<anyhow::Error>>),
+    OpenWith(PathBuf),
+    UsageStoreOpened(Result<UsageStore, Arc<anyhow::Error>>),
+    UsageHistoryLoaded(ProviderKind, UsageHistory),
+    WindowOpened(window::Id),
+    DisplayScaleChanged(f32),
 }
 
 impl Lucien {
     pub fn new() -> (Self, Task<Message>) {
         let load_preferences_task = Task::perform(Preferences::load(), Message::PreferencesLoaded);
+        let open_usage_store_task = Task::perform(UsageStore::open(), |result| {
+            Message::UsageStoreOpened(result.map_err(Arc::new))
+        });
 
         let initial_values = Self {
             selected_entry: 0,
@@ -89,15 +120,54 @@ impl Lucien {
             last_viewport: None,
             search_handle: None,
             path: PathBuf::from(env!("HOME")),
+            display_scale: 1.0,
+            icon_scale_sender: watch::Sender::new(1),
+            usage_store: None,
+            usage_history: UsageHistory::default(),
+            show_hidden_entries: false,
+            recursive_search: false,
+            search_query: String::new(),
+            open_with: None,
+            keeps_prompt: false,
         };
 
-        (initial_values, load_preferences_task)
+        (
+            initial_values,
+            Task::batch([load_preferences_task, open_usage_store_task]),
+        )
     }
 
     pub fn theme(&self) -> CustomTheme {
         self.preferences.theme.clone()
     }
 
+    /// The number of physical pixels per logical pixel icons are rendered for,
+    /// rounded up to the integer scales icon themes are organized by.
+    fn icon_scale(&self) -> u32 {
+        let scale = self.display_scale * self.preferences.scale_factor;
+        scale.ceil().max(1.0) as u32
+    }
+
+    /// Lets the running scan request its icons again when the scale changed.
+    fn publish_icon_scale(&self) {
+        let icon_scale = self.icon_scale();
+        self.icon_scale_sender
+            .send_if_modified(|scale| std::mem::replace(scale, icon_scale) != icon_scale);
+    }
+
+    /// Ranks the entries again for the current prompt, keeping the selection in bounds.
+    fn refresh_ranking(&mut self) {
+        self.entry_registry.sort_by_rank(
+            &self.preferences,
+            &self.matcher,
+            self.provider.search_query(&self.prompt),
+            &self.usage_history,
+            self.show_hidden_entries,
+        );
+        let last_index = self.entry_registry.visible_len().saturating_sub(1);
+        self.selected_entry = self.selected_entry.min(last_index);
+    }
+
     fn toggle_favorite(&mut self, index: usize) -> Task<Message> {
         let Some(app) = self.entry_registry.get_visible_by_index(index) else {
             return Task::none();
@@ -114,8 +184,7 @@ impl Lucien {
         // modifies the in-memory favorite_apps variable.
         // Maybe I should expose this assignnment operation at this level.
         let favorite_apps = self.preferences.toggle_favorite(id);
-        self.entry_registry
-            .sort_by_rank(&self.preferences, &self.matcher, &self.prompt);
+        self.refresh_ranking();
 
         Task::perform(
             preferences::save_into_disk(path, "favorite_apps", favorite_apps),
@@ -123,6 +192,130 @@ impl Lucien {
         )
     }
 
+    /// Hides the entry at `index`, or unhides it while hidden entries are listed.
+    fn toggle_hidden(&mut self, index: usize) -> Task<Message> {
+        let Some(entry) = self.entry_registry.get_visible_by_index(index) else {
+            return Task::none();
+        };
+
+        let Some(ref path) = self.preferences.path else {
+            tracing::warn!("In-memory defaults. Settings will not be saved");
+            return Task::none();
+        };
+
+        let path = path.clone();
+        let hidden_entries = if self.show_hidden_entries {
+            self.preferences
+                .hidden_entries
+                .unhide(&entry.id, &entry.main)
+        } else {
+            self.preferences.hidden_entries.hide(&entry.id)
+        };
+        self.refresh_ranking();
+
+        Task::perform(
+            preferences::save_into_disk(path, "hidden_entries", hidden_entries),
+            Message::SaveIntoDisk,
+        )
+    }
+
+    fn toggle_hidden_entries(&mut self) -> Task<Message> {
+        self.show_hidden_entries = !self.show_hidden_entries;
+        self.selected_entry = 0;
+        self.refresh_ranking();
+
+        widget::operation::snap_to(SCROLLABLE_ID.clone(), RelativeOffset::START)
+    }
+
+    fn toggle_recursive_search(&mut self) -> Task<Message> {
+        self.recursive_search = !self.recursive_search;
+        self.search_query = self.prompt.clone();
+        Task::none()
+    }
+
+    /// Changes the listing preferences of the file provider for this session,
+    /// which lists the directory again, keeping the prompt.
+    fn update_file_listing(
+        &mut self,
+        update: impl FnOnce(&mut preferences::FilePreferences),
+    ) -> Task<Message> {
+        if self.provider != ProviderKind::File {
+            return Task::none();
+        }
+
+        update(&mut self.preferences.providers.file);
+        self.keeps_prompt = true;
+        tracing::debug!(preferences = ?self.preferences.providers.file, "File listing changed.");
+        Task::none()
+    }
+
+    fn is_searching_recursively(&self) -> bool {
+        self.provider == ProviderKind::File && self.recursive_search && self.open_with.is_none()
+    }
+
+    /// The query handed to the file scan, which searches recursively while set.
+    fn recursive_query(&self) -> Option<String> {
+        let query = self.search_query.trim();
+        (self.is_searching_recursively() && !query.is_empty()).then(|| query.to_string())
+    }
+
+    /// Lists the applications able to open the file at `index`, or goes back to
+    /// the files while they are listed.
+    fn toggle_open_with(&mut self, index: usize) -> Task<Message> {
+        if self.open_with.take().is_some() {
+            return Task::none();
+        }
+
+        if self.provider != ProviderKind::File {
+            return Task::none();
+        }
+
+        let Some(entry) = self.entry_registry.get_visible_by_index(index) else {
+            return Task::none();
+        };
+
+        let path = PathBuf::from(&entry.id);
+        Task::future(async move {
+            let is_dir = tokio::fs::metadata(&path)
+                .await
+                .is_ok_and(|metadata| metadata.is_dir());
+            if is_dir {
+                tracing::debug!(
+                    ?path,
+                    "Directories are browsed, not opened with an application."
+                );
+                return None;
+            }
+
+            Some(path)
+        })
+        .and_then(|path| Task::done(Message::OpenWith(path)))
+    }
+
+    /// Makes the application at `index` the default for the type of the file
+    /// being opened, then opens it.
+    fn set_default_app(&mut self, index: usize) -> Task<Message> {
+        let Some(file) = &self.open_with else {
+            return Task::none();
+        };
+
+        let Some(entry) = self.entry_registry.get_visible_by_index(index) else {
+            return Task::none();
+        };
+
+        let file = file.clone();
+        let desktop_id = entry.id.clone();
+        let save_task = Task::perform(
+            async move {
+                let mime = mime::detect(file).await;
+                mime_apps::set_default(mime, desktop_id).await
+            },
+            Message::SaveIntoDisk,
+        );
+
+        save_task.chain(self.launch_entry(index))
+    }
+
     fn go_to_entry(&mut self, step: isize) -> Task<Message> {
         let total = self.entry_registry.visible_len();
         if total == 0 {
@@ -133,24 +326,94 @@ impl Lucien {
         self.selected_entry = wrapped_index(self.selected_entry, total, step);
 
         if old_pos != self.selected_entry {
-            let layout = AppLayout::new(&self.preferences, &self.prompt);
+            let is_filtered = !self.prompt.is_empty() || self.show_hidden_entries;
+            let layout = AppLayout::new(&self.preferences, is_filtered);
             return self.snap_to_entry(&layout);
         }
 
         Task::none()
     }
 
-    fn launch_entry(&self, index: usize) -> Task<Message> {
-        if let Some(entry) = &self.entry_registry.get_visible_by_index(index) {
-            return self.provider.launch(&entry);
+    fn launch_entry(&mut self, index: usize) -> Task<Message> {
+        let Some(entry) = self.entry_registry.get_visible_by_index(index) else {
+            return Task::none();
         };
 
-        Task::none()
+        let launch_task = self.provider.launch(entry, &self.prompt);
+        let id = entry.id.clone();
+        self.record_launch(id, launch_task)
+    }
+
+    /// Runs the entry at `index` in the configured terminal, for providers listing commands.
+    fn launch_in_terminal(&mut self, index: usize) -> Task<Message> {
+        let Some(entry) = self.entry_registry.get_visible_by_index(index) else {
+            return Task::none();
+        };
+
+        let Some(terminal) = resolve_terminal(self.preferences.terminal.as_deref()) else {
+            return Task::none();
+        };
+
+        let Some(launch_task) = self
+            .provider
+            .launch_in_terminal(entry, &self.prompt, &terminal)
+        else {
+            tracing::debug!(provider = %self.provider, "Provider cannot launch in a terminal.");
+            return Task::none();
+        };
+
+        let id = entry.id.clone();
+        self.record_launch(id, launch_task)
+    }
+
+    fn record_launch(&mut self, id: String, launch_task: Task<Message>) -> Task<Message> {
+        let Some(usage_store) = self.usage_store.clone() else {
+            return launch_task;
+        };
+
+        if !self.provider.records_usage() {
+            return launch_task;
+        }
+
+        let query = self.provider.search_query(&self.prompt).to_string();
+        self.usage_history.record(&id, &query);
+        // Recorded before the launch task runs, as it usually closes the window.
+        Task::future(usage_store.record(self.provider, id, query))
+            .discard()
+            .chain(launch_task)
+    }
+
+    fn load_usage_history(&self) -> Task<Message> {
+        let Some(usage_store) = self.usage_store.clone() else {
+            return Task::none();
+        };
+
+        let provider = self.provider;
+        Task::perform(usage_store.history(provider), move |usage_history| {
+            Message::UsageHistoryLoaded(provider, usage_history)
+        })
+    }
+
+    /// Stops boosting the entry learned for the current query.
+    fn forget_association(&mut self) -> Task<Message> {
+        let query = self.provider.search_query(&self.prompt).to_string();
+        if !self.usage_history.forget(&query) {
+            return Task::none();
+        }
+
+        self.refresh_ranking();
+        let Some(usage_store) = self.usage_store.clone() else {
+            return Task::none();
+        };
+
+        Task::future(usage_store.forget(self.provider, query)).discard()
     }
 
     fn change_provider(&mut self, provider: ProviderKind) -> Task<Message> {
         self.provider = provider;
-        Task::none()
+        self.open_with = None;
+        self.usage_history = UsageHistory::default();
+        self.load_usage_history()
     }
 
     fn handle_action(&mut self, action: Action) -> Task<Message> {
@@ -161,7 +424,26 @@ impl Lucien {
             Action::PreviousEntry => self.go_to_entry(-1),
             Action::ToggleFavorite => self.toggle_favorite(self.selected_entry),
             Action::LaunchEntry(index) => self.launch_entry(index),
+            Action::LaunchInTerminal => self.launch_in_terminal(self.selected_entry),
             Action::ChangeProvider(provider) => self.change_provider(provider),
+            Action::ForgetAssociation => self.forget_association(),
+            Action::HideEntry => self.toggle_hidden(self.selected_entry),
+            Action::ToggleHiddenEntries => self.toggle_hidden_entries(),
+            Action::ToggleRecursiveSearch => self.toggle_recursive_search(),
+            Action::OpenWith => self.toggle_open_with(self.selected_entry),
+            Action::SetDefaultApp => self.set_default_app(self.selected_entry),
+            Action::ToggleHiddenFiles => {
+                self.update_file_listing(|file| file.show_hidden = !file.show_hidden)
+            }
+            Action::CycleSortBy => {
+                self.update_file_listing(|file| file.sort_by = file.sort_by.next())
+            }
+            Action::ToggleDirectoriesFirst => self.update_file_listing(|file| {
+                file.directories_first = !file.directories_first;
+            }),
+            Action::ToggleReverseSort => {
+                self.update_file_listing(|file| file.reverse = !file.reverse)
+            }
         }
     }
 
@@ -227,6 +509,7 @@ impl Lucien {
                     Ok(preferences) => {
                         tracing::debug!("Running under user-defined preferences.");
                         self.preferences = preferences;
+                        self.publish_icon_scale();
                     }
                     Err(e) if e.kind() == std::io::ErrorKind::InvalidInput => {
                         tracing::error!(diagnostic = %e, "Invalid preferences syntax");
@@ -243,7 +526,12 @@ impl Lucien {
             }
             Message::ScanEvent(scan_event) => match scan_event {
                 ScannerState::Started => {
-                    self.prompt.clear();
+                    // Recursive searches restart as the query is typed.
+                    let keeps_prompt = std::mem::take(&mut self.keeps_prompt);
+                    if !self.is_searching_recursively() && !keeps_prompt {
+                        self.prompt.clear();
+                        self.search_query.clear();
+                    }
                     self.selected_entry = 0;
                     self.entry_registry.clear();
 
@@ -252,10 +540,26 @@ impl Lucien {
                 }
                 ScannerState::Found(batch) => {
                     self.entry_registry.extend(batch);
+                    if self.is_searching_recursively() {
+                        self.refresh_ranking();
+                    }
                     Task::none()
                 }
                 ScannerState::Finished => {
                     self.is_scan_completed = true;
+                    self.refresh_ranking();
+                    Task::none()
+                }
+                ScannerState::Updated(entries) => {
+                    for entry in entries {
+                        self.entry_registry.upsert(entry);
+                    }
+                    self.refresh_ranking();
+                    Task::none()
+                }
+                ScannerState::Removed(ids) => {
+                    self.entry_registry.remove(&ids);
+                    self.refresh_ranking();
                     Task::none()
                 }
                 ScannerState::Errored(error) => {
@@ -263,9 +567,11 @@ impl Lucien {
                     Task::none()
                 }
             },
-            Message::IconResolved { id, handle } => {
-                if let Some(entry) = self.entry_registry.get_mut_by_id(&id) {
-                    entry.icon = EntryIcon::Handle(handle);
+            Message::IconsResolved(icons) => {
+                for (id, handle) in icons {
+                    if let Some(entry) = self.entry_registry.get_mut_by_id(&id) {
+                        entry.icon = EntryIcon::Handle(handle);
+                    }
                 }
                 Task::none()
             }
@@ -307,9 +613,9 @@ impl Lucien {
                 task
             }
             Message::DebouncedFilter => {
+                self.search_query = self.prompt.clone();
                 self.selected_entry = 0;
-                self.entry_registry
-                    .sort_by_rank(&self.preferences, &self.matcher, &self.prompt);
+                self.refresh_ranking();
 
                 widget::operation::snap_to(SCROLLABLE_ID.clone(), RelativeOffset::START)
             }
@@ -329,6 +635,56 @@ impl Lucien {
             }
             Message::ChangePath(path) => {
                 self.path = path;
+                self.open_with = None;
+                // A directory opened from search results is listed, not searched.
+                self.prompt.clear();
+                self.search_query.clear();
+                Task::none()
+            }
+            Message::OpenFile(path) => {
+                let terminal = self.preferences.terminal.clone();
+                let locale = self.preferences.locale.clone();
+                Task::perform(
+                    mime_apps::open_with_default(path, terminal, locale),
+                    |result| Message::FileOpened(result.map_err(Arc::new)),
+                )
+            }
+            Message::FileOpened(result) => match result {
+                Ok(()) => window::latest().and_then(window::close),
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to open file.");
+                    Task::none()
+                }
+            },
+            Message::OpenWith(path) => {
+                // The provider may have been switched while the file was checked.
+                if self.provider == ProviderKind::File {
+                    self.open_with = Some(path);
+                }
+                Task::none()
+            }
+            Message::UsageStoreOpened(result) => match result {
+                Ok(usage_store) => {
+                    self.usage_store = Some(usage_store);
+                    self.load_usage_history()
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Usage history unavailable, ranking without frecency");
+                    Task::none()
+                }
+            },
+            Message::UsageHistoryLoaded(provider, usage_history) => {
+                if provider == self.provider {
+                    self.usage_history = usage_history;
+                    self.refresh_ranking();
+                }
+                Task::none()
+            }
+            Message::WindowOpened(id) => window::scale_factor(id).map(Message::DisplayScaleChanged),
+            Message::DisplayScaleChanged(scale) => {
+                tracing::debug!(scale, "Display scale factor changed.");
+                self.display_scale = scale;
+                self.publish_icon_scale();
                 Task::none()
             }
         }
@@ -342,11 +698,14 @@ impl Lucien {
             path: self.path.clone(),
             provider: self.provider,
             preferences: self.preferences.clone(),
+            icon_scale: self.icon_scale_sender.subscribe(),
+            query: self.recursive_query(),
+            open_with: self.open_with.clone(),
         };
 
         Subscription::batch([
             scan_request.subscribe(),
-            event::listen_with(move |event, _, _| match event {
+            event::listen_with(move |event, _, id| match event {
                 IcedKeyboardEvent(keyboard::Event::KeyPressed { modifiers, key, .. }) => {
                     let keystrokes = Keystrokes::from_iced_keystrokes(modifiers, key);
                     Some(Message::TriggerActionByKeybinding(keystrokes))
@@ -354,6 +713,10 @@ impl Lucien {
                 IcedWindowEvent(window::Event::Unfocused) => {
                     Some(Message::TriggerAction(Action::Close))
                 }
+                IcedWindowEvent(window::Event::Opened { .. }) => Some(Message::WindowOpened(id)),
+                IcedWindowEvent(window::Event::Rescaled(scale)) => {
+                    Some(Message::DisplayScaleChanged(scale))
+                }
                 _ => None,
             }),
         ])
@@ -363,7 +726,8 @@ impl Lucien {
         let theme = &self.preferences.theme;
         let item_height = theme.launchpad.entry.height;
         let style = &self.preferences.theme.launchpad.entry;
-        let show_sections = self.prompt.is_empty() && !self.preferences.favorite_apps.is_empty();
+        let is_filtered = !self.prompt.is_empty() || self.show_hidden_entries;
+        let show_sections = !is_filtered && !self.preferences.favorite_apps.is_empty();
 
         let mut starred_column = Column::new()
             .spacing(item_height * 0.1)
@@ -379,7 +743,7 @@ impl Lucien {
 
             let entry_view = mouse_area(
                 container(ui::entry::display_entry(
-                    &entry,
+                    entry,
                     style,
                     index,
                     is_selected,
@@ -392,7 +756,7 @@ impl Lucien {
             .on_enter(Message::HoveredEntry(index))
             .on_exit(Message::HoveredExit(index));
 
-            if is_favorite && self.prompt.is_empty() {
+            if is_favorite && !is_filtered {
                 starred_column = starred_column.push(entry_view);
             } else {
                 general_column = general_column.push(entry_view);
@@ -435,7 +799,17 @@ impl Lucien {
             .then(|| widget::rule::horizontal(1))
             .map(Element::from);
 
+        let placeholder = if self.show_hidden_entries {
+            "Search hidden entries..."
+        } else if self.open_with.is_some() {
+            "Open with..."
+        } else if self.is_searching_recursively() {
+            "Search files recursively..."
+        } else {
+            "Search..."
+        };
         let prompt = Prompt::new(&self.prompt, &self.preferences.theme)
+            .placeholder(placeholder)
             .indicator(self.provider_indicator())
             .magnifier(MAGNIFIER.clone())
             .id(TEXT_INPUT_ID.clone())
@@ -470,12 +844,17 @@ impl Lucien {
             ProviderKind::Clipboard => CLIPBOARD_ACTIVE.clone(),
             _ => CLIPBOARD_INACTIVE.clone(),
         };
+        let terminal_icon = match self.provider {
+            ProviderKind::Run => TERMINAL_ACTIVE.clone(),
+            _ => TERMINAL_INACTIVE.clone(),
+        };
 
         container(
             row![
                 image(apps_icon).width(18).height(18),
                 image(folder_icon).width(18).height(18),
                 image(clipboard_icon).width(18).height(18),
+                image(terminal_icon).width(18).height(18),
             ]
             .spacing(10),
         )
@@ -508,12 +887,11 @@ pub struct AppLayout {
 }
 
 impl AppLayout {
-    pub fn new(preferences: &Preferences, prompt: &str) -> Self {
+    pub fn new(preferences: &Preferences, is_filtered: bool) -> Self {
         let style = &preferences.theme.launchpad;
         let item_height = style.entry.height;
         let spacing = item_height * 0.1;
         let fav_count = preferences.favorite_apps.len();
-        let is_filtered = !prompt.is_empty();
         let has_favorites = fav_count > 0;
 
         let header_h = if !is_filtered && has_favorites {
@@ -101,10 +101,10 @@ impl FromStr for Key {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut chars = s.chars();
 
-        if let (Some(c), None) = (chars.next(), chars.next()) {
-            if c.is_alphanumeric() {
-                return Ok(Key::Character(c));
-            }
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && c.is_alphanumeric()
+        {
+            return Ok(Key::Character(c));
         }
 
         match s {
@@ -131,6 +131,17 @@ pub enum Action {
     NextEntry,
     PreviousEntry,
     LaunchEntry(usize),
+    LaunchInTerminal,
+    ForgetAssociation,
+    HideEntry,
+    ToggleHiddenEntries,
+    ToggleRecursiveSearch,
+    OpenWith,
+    SetDefaultApp,
+    ToggleHiddenFiles,
+    CycleSortBy,
+    ToggleDirectoriesFirst,
+    ToggleReverseSort,
 }
 
 fn extract_parameter<T: FromStr>(parameter_part: &str) -> Result<T, String> {
@@ -156,6 +167,17 @@ impl FromStr for Action {
             "close" => Ok(Action::Close),
             "next_entry" => Ok(Action::NextEntry),
             "previous_entry" => Ok(Action::PreviousEntry),
+            "launch_in_terminal" => Ok(Action::LaunchInTerminal),
+            "forget_association" => Ok(Action::ForgetAssociation),
+            "hide_entry" => Ok(Action::HideEntry),
+            "toggle_hidden_entries" => Ok(Action::ToggleHiddenEntries),
+            "toggle_recursive_search" => Ok(Action::ToggleRecursiveSearch),
+            "open_with" => Ok(Action::OpenWith),
+            "set_default_app" => Ok(Action::SetDefaultApp),
+            "toggle_hidden_files" => Ok(Action::ToggleHiddenFiles),
+            "cycle_sort_by" => Ok(Action::CycleSortBy),
+            "toggle_directories_first" => Ok(Action::ToggleDirectoriesFirst),
+            "toggle_reverse_sort" => Ok(Action::ToggleReverseSort),
             "launch_entry" if param.ends_with(")") => {
                 let index: usize = extract_parameter(param)?;
                 Ok(Action::LaunchEntry(index))
@@ -166,7 +188,11 @@ impl FromStr for Action {
             }
             _ => Err(format!(
                 "unknown action '{action}'. Available actions are: 'toggle_favorite', \
-                'close', 'next_entry', 'previous_entry', 'launch_entry(index)'"
+                'close', 'next_entry', 'previous_entry', 'launch_in_terminal', \
+                'forget_association', 'hide_entry', 'toggle_hidden_entries', \
+                'toggle_recursive_search', 'open_with', 'set_default_app', \
+                'toggle_hidden_files', 'cycle_sort_by', 'toggle_directories_first', \
+                'toggle_reverse_sort', 'launch_entry(index)'"
             )),
         }
     }
@@ -186,6 +212,17 @@ impl Serialize for Action {
             Action::NextEntry => serializer.serialize_str("next_entry"),
             Action::PreviousEntry => serializer.serialize_str("previous_entry"),
             Action::LaunchEntry(n) => serializer.serialize_str(&format!("launch_entry({n})")),
+            Action::LaunchInTerminal => serializer.serialize_str("launch_in_terminal"),
+            Action::ForgetAssociation => serializer.serialize_str("forget_association"),
+            Action::HideEntry => serializer.serialize_str("hide_entry"),
+            Action::ToggleHiddenEntries => serializer.serialize_str("toggle_hidden_entries"),
+            Action::ToggleRecursiveSearch => serializer.serialize_str("toggle_recursive_search"),
+            Action::OpenWith => serializer.serialize_str("open_with"),
+            Action::SetDefaultApp => serializer.serialize_str("set_default_app"),
+            Action::ToggleHiddenFiles => serializer.serialize_str("toggle_hidden_files"),
+            Action::CycleSortBy => serializer.serialize_str("cycle_sort_by"),
+            Action::ToggleDirectoriesFirst => serializer.serialize_str("toggle_directories_first"),
+            Action::ToggleReverseSort => serializer.serialize_str("toggle_reverse_sort"),
         }
     }
 }
@@ -342,10 +379,58 @@ pub fn default_keybindings() -> HashMap<Keystrokes, Action> {
             Keystrokes::new([Modifiers::SHIFT], Key::Character('3')),
             Action::ChangeProvider(ProviderKind::Clipboard),
         ),
+        (
+            Keystrokes::new([Modifiers::SHIFT], Key::Character('4')),
+            Action::ChangeProvider(ProviderKind::Run),
+        ),
+        (
+            Keystrokes::new([Modifiers::CONTROL], Key::Character('t')),
+            Action::LaunchInTerminal,
+        ),
         (
             Keystrokes::new([Modifiers::CONTROL], Key::Character('f')),
             Action::ToggleFavorite,
         ),
+        (
+            Keystrokes::new([Modifiers::CONTROL], Key::Character('d')),
+            Action::ForgetAssociation,
+        ),
+        (
+            Keystrokes::new([Modifiers::CONTROL], Key::Character('h')),
+            Action::HideEntry,
+        ),
+        (
+            Keystrokes::new([Modifiers::ALT], Key::Character('h')),
+            Action::ToggleHiddenEntries,
+        ),
+        (
+            Keystrokes::new([Modifiers::CONTROL], Key::Character('r')),
+            Action::ToggleRecursiveSearch,
+        ),
+        (
+            Keystrokes::new([Modifiers::CONTROL], Key::Character('o')),
+            Action::OpenWith,
+        ),
+        (
+            Keystrokes::new([Modifiers::CONTROL], Key::Character('s')),
+            Action::SetDefaultApp,
+        ),
+        (
+            Keystrokes::new([Modifiers::ALT], Key::Character('a')),
+            Action::ToggleHiddenFiles,
+        ),
+        (
+            Keystrokes::new([Modifiers::ALT], Key::Character('s')),
+            Action::CycleSortBy,
+        ),
+        (
+            Keystrokes::new([Modifiers::ALT], Key::Character('d')),
+            Action::ToggleDirectoriesFirst,
+        ),
+        (
+            Keystrokes::new([Modifiers::ALT], Key::Character('r')),
+            Action::ToggleReverseSort,
+        ),
         (Keystrokes::new([], Key::Tab), Action::NextEntry),
         (Keystrokes::new([], Key::Down), Action::NextEntry),
         (
@@ -389,7 +474,7 @@ pub fn extend_keybindings(extended_keybindings: Keybindings) -> Keybindings {
 
 fn check_keybinding_overrides(base_keybindings: &Keybindings, extended_keybindings: &Keybindings) {
     for (keystroke, new_action) in extended_keybindings.iter() {
-        if let Some(old_action) = base_keybindings.get(&keystroke) {
+        if let Some(old_action) = base_keybindings.get(keystroke) {
             tracing::debug!(
                 "Overriding default keybinding '{keystroke}': '{old_action:?}' -> '{new_action:?}'"
             );
@@ -1,4 +1,10 @@
-use std::{collections::HashSet, env, path::PathBuf, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    env,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use serde::{Deserialize, Serialize};
 use tokio::io;
@@ -10,6 +16,8 @@ pub mod theme;
 use keybindings::{Keybindings, default_keybindings, extend_keybindings};
 use theme::CustomTheme;
 
+use crate::providers::app::installed_launchables;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Preferences {
@@ -17,18 +25,188 @@ pub struct Preferences {
     pub path: Option<PathBuf>,
     pub scan_batch_size: usize,
     pub scale_factor: f32,
+    pub locale: Option<String>,
+    pub terminal: Option<String>,
+    pub show_broken_apps: bool,
+    pub icon_theme: Option<String>,
     pub favorite_apps: HashSet<String>,
+    pub hidden_entries: HiddenEntries,
+    pub apps: HashMap<String, AppPreferences>,
+    pub custom_entries: Vec<CustomEntry>,
+    pub providers: ProvidersPreferences,
     pub theme: CustomTheme,
     pub keybindings: Keybindings,
 }
 
+/// Glob patterns, such as `org.kde.*.desktop` or `Java*`, matched against
+/// the ID and the name of every entry to leave it out of the results.
+#[derive(Debug, Clone, Default)]
+pub struct HiddenEntries(Vec<glob::Pattern>);
+
+impl HiddenEntries {
+    pub fn matches(&self, id: &str, name: &str) -> bool {
+        self.0
+            .iter()
+            .any(|pattern| pattern.matches(id) || pattern.matches(name))
+    }
+
+    /// Hides the entry with `id`, returning the updated list to save.
+    pub fn hide(&mut self, id: &str) -> toml_edit::Array {
+        if let Ok(pattern) = glob::Pattern::new(&glob::Pattern::escape(id)) {
+            self.0.push(pattern);
+        }
+
+        self.to_array()
+    }
+
+    /// Removes every pattern matching the entry, which may unhide other entries
+    /// matched by the same glob, returning the updated list to save.
+    pub fn unhide(&mut self, id: &str, name: &str) -> toml_edit::Array {
+        self.0
+            .retain(|pattern| !pattern.matches(id) && !pattern.matches(name));
+
+        self.to_array()
+    }
+
+    fn to_array(&self) -> toml_edit::Array {
+        toml_edit::Array::from_iter(self.0.iter().map(glob::Pattern::as_str))
+    }
+}
+
+impl Serialize for HiddenEntries {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_seq(self.0.iter().map(glob::Pattern::as_str))
+    }
+}
+
+impl<'de> Deserialize<'de> for HiddenEntries {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|pattern| {
+                glob::Pattern::new(pattern).map_err(|e| {
+                    serde::de::Error::custom(format!("invalid hidden entry '{pattern}': {e}"))
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// A launcher item defined in the preferences instead of a desktop file.
+/// Its name identifies it, so it must be unique among custom entries.
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+pub struct CustomEntry {
+    pub name: String,
+    pub comment: Option<String>,
+    /// An icon name looked up in the icon theme, or an absolute path.
+    pub icon: Option<String>,
+    /// Split into arguments like the `Exec` key of a desktop file, but not run
+    /// by a shell. `%%` stands for a percent sign.
+    pub command: String,
+}
+
+/// Launch settings of a single application, keyed by its desktop-file ID.
+/// Settings of an application also apply to its desktop actions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppPreferences {
+    pub env: HashMap<String, String>,
+    /// Command the application is run through, e.g. `["prime-run"]`.
+    pub wrapper: Vec<String>,
+    /// Arguments appended to the application command.
+    pub args: Vec<String>,
+}
+
+impl Hash for AppPreferences {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.env.iter().collect::<BTreeMap<_, _>>().hash(state);
+        self.wrapper.hash(state);
+        self.args.hash(state);
+    }
+}
+
+/// Settings of each provider, under `[providers.<name>]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProvidersPreferences {
+    pub file: FilePreferences,
+}
+
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilePreferences {
+    /// Gitignore-style globs left out of recursive searches, e.g. `node_modules/`.
+    pub exclude: Vec<String>,
+    /// How many directories below the current one recursive searches descend.
+    pub max_depth: usize,
+    /// Lists dotfiles, and lets recursive searches descend into dot directories.
+    pub show_hidden: bool,
+    /// What directory listings are ordered by.
+    pub sort_by: SortBy,
+    /// Lists directories before files, whatever the order.
+    pub directories_first: bool,
+    /// Reverses the order within directories and within files.
+    pub reverse: bool,
+}
+
+impl Default for FilePreferences {
+    fn default() -> Self {
+        Self {
+            exclude: Vec::new(),
+            max_depth: 8,
+            show_hidden: false,
+            sort_by: SortBy::default(),
+            directories_first: true,
+            reverse: false,
+        }
+    }
+}
+
+/// Order of directory listings. Recent and large files come first, like `ls`.
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    #[default]
+    Name,
+    Mtime,
+    Size,
+    Extension,
+}
+
+impl SortBy {
+    /// The order after this one, cycling back to the first.
+    pub fn next(self) -> Self {
+        match self {
+            SortBy::Name => SortBy::Mtime,
+            SortBy::Mtime => SortBy::Size,
+            SortBy::Size => SortBy::Extension,
+            SortBy::Extension => SortBy::Name,
+        }
+    }
+}
+
 impl Default for Preferences {
     fn default() -> Self {
         Self {
             path: None,
             scale_factor: 1.0,
+            locale: None,
+            terminal: None,
+            show_broken_apps: false,
+            icon_theme: None,
             scan_batch_size: 10,
             favorite_apps: HashSet::new(),
+            hidden_entries: HiddenEntries::default(),
+            apps: HashMap::new(),
+            custom_entries: Vec::new(),
+            providers: ProvidersPreferences::default(),
             theme: CustomTheme::default(),
             keybindings: default_keybindings(),
         }
@@ -36,6 +214,20 @@ impl Default for Preferences {
 }
 
 impl Preferences {
+    /// Hashes every setting a provider scan reads, so a scan started before
+    /// the preferences loaded, or before they changed, starts again.
+    pub fn hash_scan_settings<H: Hasher>(&self, state: &mut H) {
+        self.scan_batch_size.hash(state);
+        self.locale.hash(state);
+        self.terminal.hash(state);
+        self.show_broken_apps.hash(state);
+        self.icon_theme.hash(state);
+        self.apps.iter().collect::<BTreeMap<_, _>>().hash(state);
+        self.custom_entries.hash(state);
+        self.providers.file.hash(state);
+        self.theme.launchpad.entry.icon_size.hash(state);
+    }
+
     // TODO (Dependency injection): I should pass the preferences file path
     // as an argument. Support a --preference-path="arbitrary/file/path.toml".
     // Also, it's going to be easier to test.
@@ -49,9 +241,33 @@ impl Preferences {
         let mut preferences = toml::from_str::<Preferences>(&settings_file_string)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
 
-        preferences.path = Some(settings_file_path);
         preferences.keybindings = extend_keybindings(preferences.keybindings);
 
+        let mut custom_names = HashSet::new();
+        preferences.custom_entries.retain(|custom_entry| {
+            let is_unique = custom_names.insert(custom_entry.name.clone());
+            if !is_unique {
+                tracing::warn!(
+                    name = custom_entry.name,
+                    "Skipping custom entry sharing its name with a previous one."
+                );
+            }
+            is_unique
+        });
+        // Reading every desktop file is only worth it when there are favorites.
+        if !preferences.favorite_apps.is_empty()
+            && let Some(favorite_apps) =
+                preferences.migrate_favorites(&installed_launchables().await)
+        {
+            tracing::info!("Migrating favorite apps from Exec values to desktop-file IDs.");
+            if let Err(e) =
+                save_into_disk(settings_file_path.clone(), "favorite_apps", favorite_apps).await
+            {
+                tracing::warn!(error = %e, "Failed to save migrated favorite apps");
+            }
+        }
+        preferences.path = Some(settings_file_path);
+
         Ok(preferences)
     }
 
@@ -63,6 +279,35 @@ impl Preferences {
 
         toml_edit::Array::from_iter(&self.favorite_apps)
     }
+
+    /// Replaces the favorites that are the `Exec` value of an installed application,
+    /// as apps were identified before desktop-file IDs, with its entry ID, given
+    /// the `(entry ID, Exec)` of every launchable by precedence. Favorites that
+    /// are already app entry IDs, or come from other providers, are kept.
+    /// Returns `None` when no favorite needed to change.
+    fn migrate_favorites(&mut self, launchables: &[(String, String)]) -> Option<toml_edit::Array> {
+        let app_ids: HashSet<&str> = launchables.iter().map(|(id, _)| id.as_str()).collect();
+        let mut ids_by_exec = HashMap::new();
+        for (id, exec) in launchables {
+            // The first app by precedence claims an Exec value.
+            ids_by_exec.entry(exec.as_str()).or_insert(id.as_str());
+        }
+
+        let mut has_changed = false;
+        self.favorite_apps = self
+            .favorite_apps
+            .drain()
+            .map(|id| match ids_by_exec.get(id.as_str()) {
+                Some(app_id) if !app_ids.contains(id.as_str()) => {
+                    has_changed = true;
+                    app_id.to_string()
+                }
+                _ => id,
+            })
+            .collect();
+
+        has_changed.then(|| toml_edit::Array::from_iter(&self.favorite_apps))
+    }
 }
 
 pub async fn save_into_disk(
@@ -85,3 +330,58 @@ pub async fn save_into_disk(
     tokio::fs::rename(&tmp_path, &path).await?;
     Ok(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launchables() -> Vec<(String, String)> {
+        [
+            ("firefox.desktop", "firefox %u"),
+            ("firefox.desktop:new-window", "firefox --new-window %u"),
+            ("portable.desktop", "/opt/tools/run.desktop --portable"),
+            ("shadowed.desktop", "firefox %u"),
+        ]
+        .into_iter()
+        .map(|(id, exec)| (id.to_string(), exec.to_string()))
+        .collect()
+    }
+
+    fn favorites(ids: &[&str]) -> Preferences {
+        Preferences {
+            favorite_apps: ids.iter().map(|id| id.to_string()).collect(),
+            ..Preferences::default()
+        }
+    }
+
+    #[test]
+    fn exec_favorites_become_app_entry_ids() {
+        let mut preferences = favorites(&[
+            "firefox %u",
+            "firefox --new-window %u",
+            "/opt/tools/run.desktop --portable",
+        ]);
+
+        assert!(preferences.migrate_favorites(&launchables()).is_some());
+        assert_eq!(
+            preferences.favorite_apps,
+            favorites(&[
+                "firefox.desktop",
+                "firefox.desktop:new-window",
+                "portable.desktop"
+            ])
+            .favorite_apps
+        );
+    }
+
+    #[test]
+    fn current_and_other_provider_favorites_are_kept() {
+        let mut preferences = favorites(&["firefox.desktop", "/home/user/notes.txt", "custom:VPN"]);
+
+        assert!(preferences.migrate_favorites(&launchables()).is_none());
+        assert_eq!(
+            preferences.favorite_apps,
+            favorites(&["firefox.desktop", "/home/user/notes.txt", "custom:VPN"]).favorite_apps
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use std::path::{Path, PathBuf};
+
+use super::exec;
+use super::locale::{Locale, Localized, UNLOCALIZED_RANK};
+use crate::providers::is_program_available;
+
+const ACTION_SEPARATOR: &str = " › ";
+/// Joins a desktop-file ID and an action ID into the ID of the action entry.
+const ACTION_ID_SEPARATOR: char = ':';
+/// Keys taking a `[locale]` suffix. Localized forms of any other key are ignored.
+const LOCALIZED_KEYS: [&str; 4] = ["Name", "GenericName", "Comment", "Keywords"];
+
+#[derive(Default)]
+pub struct App {
+    /// The desktop-file ID, unique among the installed applications.
+    pub id: String,
+    pub path: PathBuf,
+    pub name: String,
+    pub exec: String,
+    pub try_exec: Option<String>,
+    pub comment: Option<String>,
+    pub generic_name: Option<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub icon: Option<String>,
+    pub terminal: bool,
+    pub prefers_non_default_gpu: bool,
+    pub working_dir: Option<PathBuf>,
+    pub actions: Vec<DesktopAction>,
+    /// Types the application can open, from the `MimeType` key.
+    pub mime_types: Vec<String>,
+    /// Kept out of the launcher by `NoDisplay`, `OnlyShowIn` or `NotShowIn`,
+    /// but still able to open files.
+    pub no_display: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DesktopAction {
+    /// The action group ID, or once returned by `App::launchables`, the entry ID.
+    pub id: String,
+    pub name: String,
+    pub exec: String,
+    pub icon: Option<String>,
+}
+
+impl App {
+    /// The application itself followed by each of its `[Desktop Action]` groups.
+    /// Actions are named after their application and inherit its icon, and their
+    /// IDs are scoped by the desktop-file ID, e.g. `firefox.desktop:new-window`.
+    pub fn launchables(&self) -> impl Iterator<Item = DesktopAction> + '_ {
+        let main = DesktopAction {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            exec: self.exec.clone(),
+            icon: self.icon.clone(),
+        };
+        let actions = self.actions.iter().map(|action| DesktopAction {
+            id: format!("{}{ACTION_ID_SEPARATOR}{}", self.id, action.id),
+            name: format!("{}{ACTION_SEPARATOR}{}", self.name, action.name),
+            exec: action.exec.clone(),
+            icon: action.icon.clone().or_else(|| self.icon.clone()),
+        });
+
+        std::iter::once(main).chain(actions)
+    }
+
+    /// Whether both `TryExec` and the program in `Exec` can be found, which
+    /// tells apart desktop files left behind by uninstalled packages.
+    pub fn is_installed(&self) -> bool {
+        let try_exec_found = self.try_exec.as_deref().is_none_or(is_program_available);
+        let exec_found = exec::program(&self.exec).is_some_and(|p| is_program_available(&p));
+
+        try_exec_found && exec_found
+    }
+}
+
+enum Group {
+    Main,
+    Action(usize),
+    Other,
+}
+
+#[derive(Default)]
+struct ActionGroup {
+    id: String,
+    name: Localized,
+    exec: Option<String>,
+    icon: Option<String>,
+}
+
+pub fn parse_desktop_entry(
+    content: &str,
+    current_desktops: &[String],
+    locale: Option<&Locale>,
+) -> Option<App> {
+    let mut app = App::default();
+    let mut group = Group::Other;
+
+    let mut name = Localized::default();
+    let mut comment = Localized::default();
+    let mut generic_name = Localized::default();
+    let mut keywords = Localized::default();
+    let mut action_ids = Vec::new();
+    let mut action_groups: Vec<ActionGroup> = Vec::new();
+    let mut has_exec = false;
+    let mut has_type = false;
+    let mut is_hidden = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            let header = line.trim_start_matches('[').trim_end_matches(']');
+            group = if header == "Desktop Entry" {
+                Group::Main
+            } else if let Some(id) = header.strip_prefix("Desktop Action ") {
+                action_groups.push(ActionGroup {
+                    id: id.to_string(),
+                    ..Default::default()
+                });
+                Group::Action(action_groups.len() - 1)
+            } else {
+                Group::Other
+            };
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let raw_value = value.trim();
+        // `Exec` is unescaped by its own parser, lists while they are split.
+        let value = exec::unescape_string(raw_value);
+        let value = value.as_str();
+        let (key, rank) = match key.trim().split_once('[') {
+            Some((key, key_locale)) => {
+                if !LOCALIZED_KEYS.contains(&key) {
+                    continue;
+                }
+
+                let key_locale = key_locale.strip_suffix(']').unwrap_or(key_locale);
+                match locale.and_then(|locale| locale.match_rank(key_locale)) {
+                    Some(rank) => (key, rank),
+                    None => continue,
+                }
+            }
+            None => (key.trim(), UNLOCALIZED_RANK),
+        };
+
+        match group {
+            Group::Main => match key {
+                "Type" => {
+                    if value != "Application" {
+                        return None;
+                    }
+                    has_type = true;
+                }
+                "Hidden" if value == "true" => is_hidden = true,
+                "NoDisplay" if value == "true" => app.no_display = true,
+                "OnlyShowIn" => {
+                    let is_match = split_list(raw_value)
+                        .iter()
+                        .any(|d| current_desktops.contains(d));
+
+                    if !is_match {
+                        app.no_display = true;
+                    }
+                }
+                "NotShowIn" => {
+                    let is_match = split_list(raw_value)
+                        .iter()
+                        .any(|d| current_desktops.contains(d));
+
+                    if is_match {
+                        app.no_display = true;
+                    }
+                }
+                "Name" => name.offer(value, rank),
+                "Exec" => {
+                    app.exec = raw_value.to_string();
+                    has_exec = true;
+                }
+                "TryExec" => app.try_exec = Some(value.to_string()),
+                "Icon" => app.icon = Some(value.to_string()),
+                "Terminal" => app.terminal = value == "true",
+                "PrefersNonDefaultGPU" => app.prefers_non_default_gpu = value == "true",
+                "Path" if !value.is_empty() => app.working_dir = Some(PathBuf::from(value)),
+                "Comment" => comment.offer(value, rank),
+                "GenericName" => generic_name.offer(value, rank),
+                "Keywords" => keywords.offer(raw_value, rank),
+                "Categories" => app.categories = split_list(raw_value),
+                "Actions" => action_ids = split_list(raw_value),
+                "MimeType" => app.mime_types = split_list(raw_value),
+                _ => {}
+            },
+            Group::Action(index) => {
+                let action = &mut action_groups[index];
+                match key {
+                    "Name" => action.name.offer(value, rank),
+                    "Exec" => action.exec = Some(raw_value.to_string()),
+                    "Icon" => action.icon = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+            Group::Other => {}
+        }
+    }
+
+    app.comment = comment.into_inner();
+    app.generic_name = generic_name.into_inner();
+    app.keywords = keywords
+        .into_inner()
+        .map(|keywords| split_list(&keywords))
+        .unwrap_or_default();
+    // Only groups listed in `Actions=` are valid, in the order they are listed.
+    app.actions = action_ids
+        .iter()
+        .filter_map(|id| {
+            let group = action_groups.iter_mut().find(|group| group.id == *id)?;
+            Some(DesktopAction {
+                id: id.clone(),
+                name: std::mem::take(&mut group.name).into_inner()?,
+                exec: group.exec.take()?,
+                icon: group.icon.take(),
+            })
+        })
+        .collect();
+
+    if let Some(name) = name.into_inner()
+        && !is_hidden
+        && has_exec
+        && has_type
+    {
+        app.name = name;
+        Some(app)
+    } else {
+        None
+    }
+}
+
+/// Splits a list value on `;`, resolving escapes including `\;` for a
+/// semicolon inside an item.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => items.push(std::mem::take(&mut current)),
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some('s') => current.push(' '),
+                Some('n') => current.push('\n'),
+                Some('t') => current.push('\t'),
+                Some('r') => current.push('\r'),
+                Some('\\') => current.push('\\'),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+/// Builds the desktop-file ID of `path` from its location inside an `applications`
+/// directory, e.g. `applications/kde/foo.desktop` becomes `kde-foo.desktop`.
+pub fn desktop_file_id(applications_dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(applications_dir).ok()?;
+    let components = relative
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(components.join("-"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str, locale: Option<&str>) -> Option<App> {
+        let locale = locale.and_then(Locale::parse);
+        parse_desktop_entry(content, &["GNOME".to_string()], locale.as_ref())
+    }
+
+    const FIREFOX: &str = r#"
+[Desktop Entry]
+Version=1.0
+Name=Firefox
+Name[de]=Firefox-Webbrowser
+Name[pt_BR]=Navegador Firefox
+GenericName=Web Browser
+GenericName[de]=Webbrowser
+Comment=Browse the World Wide Web
+Comment[de]=Im Internet surfen
+Keywords=Internet;WWW;Browser;Web;Explorer
+Keywords[de]=Internet;WWW;Browser;Web;Surfen
+Exec=firefox %u
+Icon=firefox
+Terminal=false
+Type=Application
+MimeType=text/html;text/xml;application/xhtml+xml;x-scheme-handler/http;
+Categories=Network;WebBrowser;
+Actions=new-window;new-private-window;
+
+[Desktop Action new-window]
+Name=Open a New Window
+Name[de]=Ein neues Fenster öffnen
+Exec=firefox --new-window %u
+
+[Desktop Action new-private-window]
+Name=Open a New Private Window
+Exec=firefox --private-window %u
+"#;
+
+    #[test]
+    fn parses_a_desktop_entry() {
+        let app = parse(FIREFOX, None).unwrap();
+        assert_eq!(app.name, "Firefox");
+        assert_eq!(app.generic_name.as_deref(), Some("Web Browser"));
+        assert_eq!(app.comment.as_deref(), Some("Browse the World Wide Web"));
+        assert_eq!(app.exec, "firefox %u");
+        assert_eq!(app.icon.as_deref(), Some("firefox"));
+        assert_eq!(app.categories, ["Network", "WebBrowser"]);
+        assert_eq!(
+            app.keywords,
+            ["Internet", "WWW", "Browser", "Web", "Explorer"]
+        );
+        assert_eq!(app.mime_types.len(), 4);
+        assert!(!app.terminal);
+
+        let actions: Vec<_> = app.actions.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(actions, ["new-window", "new-private-window"]);
+    }
+
+    #[test]
+    fn picks_the_best_localized_values() {
+        let app = parse(FIREFOX, Some("de_DE.UTF-8")).unwrap();
+        assert_eq!(app.name, "Firefox-Webbrowser");
+        assert_eq!(app.generic_name.as_deref(), Some("Webbrowser"));
+        assert_eq!(app.comment.as_deref(), Some("Im Internet surfen"));
+        assert_eq!(app.keywords.last().map(String::as_str), Some("Surfen"));
+        assert_eq!(app.actions[0].name, "Ein neues Fenster öffnen");
+        assert_eq!(app.actions[1].name, "Open a New Private Window");
+
+        let app = parse(FIREFOX, Some("pt_BR")).unwrap();
+        assert_eq!(app.name, "Navegador Firefox");
+        let app = parse(FIREFOX, Some("pt_PT")).unwrap();
+        assert_eq!(app.name, "Firefox");
+    }
+
+    #[test]
+    fn ignores_localized_forms_of_other_keys() {
+        let content = "[Desktop Entry]\nType=Application\nName=App\nExec=app\n\
+                       Exec[de]=malicious\nIcon=app\nIcon[de]=other\nTerminal[de]=true\n";
+        let app = parse(content, Some("de")).unwrap();
+        assert_eq!(app.exec, "app");
+        assert_eq!(app.icon.as_deref(), Some("app"));
+        assert!(!app.terminal);
+    }
+
+    #[test]
+    fn unescapes_values() {
+        let content = r#"[Desktop Entry]
+Type=Application
+Name=Two\sWords\tTab
+Comment=Line\nBreak and \\ backslash
+Exec=app "a\\\\b"
+Keywords=semi\;colon;plain;
+"#;
+        let app = parse(content, None).unwrap();
+        assert_eq!(app.name, "Two Words\tTab");
+        assert_eq!(app.comment.as_deref(), Some("Line\nBreak and \\ backslash"));
+        assert_eq!(app.keywords, ["semi;colon", "plain"]);
+        // Left for the Exec parser, which resolves the same escapes itself.
+        assert_eq!(app.exec, r#"app "a\\\\b""#);
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        let missing_exec = "[Desktop Entry]\nType=Application\nName=App\n";
+        let link = "[Desktop Entry]\nType=Link\nName=App\nURL=https://example.com\n";
+        let hidden = "[Desktop Entry]\nType=Application\nName=App\nExec=app\nHidden=true\n";
+        let outside_main_group = "[Other Group]\nType=Application\nName=App\nExec=app\n";
+
+        for content in [missing_exec, link, hidden, outside_main_group] {
+            assert!(parse(content, None).is_none(), "{content}");
+        }
+    }
+
+    #[test]
+    fn skips_actions_not_listed_or_incomplete() {
+        let content = "[Desktop Entry]\nType=Application\nName=App\nExec=app\n\
+                       Actions=listed;no-exec;\n\n\
+                       [Desktop Action listed]\nName=Listed\nExec=app --listed\n\n\
+                       [Desktop Action no-exec]\nName=No Exec\n\n\
+                       [Desktop Action unlisted]\nName=Unlisted\nExec=app --unlisted\n";
+        let app = parse(content, None).unwrap();
+        let actions: Vec<_> = app.actions.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(actions, ["listed"]);
+    }
+
+    #[test]
+    fn splits_lists() {
+        assert_eq!(split_list("a;b;;c;"), ["a", "b", "c"]);
+        assert_eq!(split_list(r"a\;b;c\sd"), ["a;b", "c d"]);
+        assert_eq!(split_list(r"ends\\;next"), ["ends\\", "next"]);
+        assert!(split_list("").is_empty());
+    }
+
+    #[test]
+    fn builds_desktop_file_ids() {
+        let dir = Path::new("/usr/share/applications");
+        let id = |path: &str| desktop_file_id(dir, Path::new(path));
+        assert_eq!(
+            id("/usr/share/applications/firefox.desktop").as_deref(),
+            Some("firefox.desktop")
+        );
+        assert_eq!(
+            id("/usr/share/applications/kde/org.kde.dolphin.desktop").as_deref(),
+            Some("kde-org.kde.dolphin.desktop")
+        );
+        assert_eq!(id("/opt/apps/firefox.desktop"), None);
+    }
+}
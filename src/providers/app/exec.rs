@@ -0,0 +1,338 @@
+use std::path::Path;
+
+use anyhow::bail;
+
+/// Values substituted for the field codes of an `Exec` key.
+pub struct FieldCodes<'a> {
+    pub icon: Option<&'a str>,
+    pub name: &'a str,
+    pub desktop_file: &'a Path,
+    /// The file opened with the application, if any.
+    pub file: Option<&'a str>,
+}
+
+/// Splits an `Exec` value into an argument vector following the Desktop Entry
+/// specification: string escapes are resolved first, then double quotes and
+/// backslashes are interpreted, and finally field codes are expanded.
+///
+/// `%f`, `%F`, `%u` and `%U` expand to the opened file, once. Without a file
+/// they expand to nothing, as do the deprecated and unknown codes.
+pub fn parse_exec(exec: &str, field_codes: &FieldCodes) -> anyhow::Result<Vec<String>> {
+    let unescaped = unescape_string(exec);
+    let mut chars = unescaped.chars().peekable();
+    let mut args = Vec::new();
+    let mut current = String::new();
+    // Tracks whether the current argument exists, even when it is empty (e.g. `""`).
+    let mut has_arg = false;
+    let mut in_quotes = false;
+    let mut has_file = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            '\\' if in_quotes => match chars.next() {
+                Some(escaped @ ('"' | '`' | '$' | '\\')) => current.push(escaped),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => bail!("unterminated escape sequence in quoted argument"),
+            },
+            '\\' => match chars.next() {
+                Some(escaped) => {
+                    current.push(escaped);
+                    has_arg = true;
+                }
+                None => bail!("trailing backslash"),
+            },
+            '%' => match chars.next() {
+                Some('%') => {
+                    current.push('%');
+                    has_arg = true;
+                }
+                Some('i') => {
+                    let is_standalone =
+                        !in_quotes && !has_arg && chars.peek().is_none_or(|c| *c == ' ');
+
+                    match field_codes.icon {
+                        Some(icon) if is_standalone => {
+                            args.push("--icon".to_string());
+                            args.push(icon.to_string());
+                        }
+                        Some(icon) => {
+                            current.push_str(icon);
+                            has_arg = true;
+                        }
+                        None => {}
+                    }
+                }
+                Some('c') => {
+                    current.push_str(field_codes.name);
+                    has_arg = true;
+                }
+                Some('k') => {
+                    current.push_str(&field_codes.desktop_file.to_string_lossy());
+                    has_arg = true;
+                }
+                Some('f' | 'F' | 'u' | 'U') => {
+                    if let Some(file) = field_codes.file
+                        && !has_file
+                    {
+                        current.push_str(file);
+                        has_arg = true;
+                        has_file = true;
+                    }
+                }
+                Some(_) => {}
+                None => bail!("trailing '%' without a field code"),
+            },
+            ' ' if !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            _ => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        bail!("unterminated quoted argument");
+    }
+
+    if has_arg {
+        args.push(current);
+    }
+
+    Ok(args)
+}
+
+/// Whether an `Exec` value has a field code for files or URLs, without which
+/// the application cannot be given a file to open.
+pub fn accepts_files(exec: &str) -> bool {
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '%' && matches!(chars.next(), Some('f' | 'F' | 'u' | 'U')) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Returns the program run by an `Exec` value, without expanding field codes.
+pub fn program(exec: &str) -> Option<String> {
+    let field_codes = FieldCodes {
+        icon: None,
+        name: "",
+        desktop_file: Path::new(""),
+        file: None,
+    };
+
+    parse_exec(exec, &field_codes).ok()?.into_iter().next()
+}
+
+/// Resolves the escape sequences allowed in values of type `string`.
+/// Unknown sequences are kept verbatim so the quoting rules can handle them.
+pub fn unescape_string(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESKTOP_FILE: &str = "/usr/share/applications/org.example.App.desktop";
+
+    fn field_codes(icon: Option<&'static str>) -> FieldCodes<'static> {
+        FieldCodes {
+            icon,
+            name: "Example App",
+            desktop_file: Path::new(DESKTOP_FILE),
+            file: None,
+        }
+    }
+
+    fn parse(exec: &str) -> Vec<String> {
+        parse_exec(exec, &field_codes(Some("example"))).unwrap()
+    }
+
+    #[test]
+    fn real_exec_lines() {
+        assert_eq!(
+            parse("/usr/lib/firefox/firefox %u"),
+            ["/usr/lib/firefox/firefox"]
+        );
+        assert_eq!(
+            parse("/usr/lib/firefox/firefox --private-window %u"),
+            ["/usr/lib/firefox/firefox", "--private-window"]
+        );
+        assert_eq!(
+            parse("/usr/share/code/code --unity-launch %F"),
+            ["/usr/share/code/code", "--unity-launch"]
+        );
+        assert_eq!(parse("/usr/bin/steam %U"), ["/usr/bin/steam"]);
+        assert_eq!(
+            parse("steam steam://rungameid/570"),
+            ["steam", "steam://rungameid/570"]
+        );
+        assert_eq!(
+            parse(
+                "/usr/bin/flatpak run --branch=stable --arch=x86_64 --command=gimp-2.10 \
+                 --file-forwarding org.gimp.GIMP @@u %U @@"
+            ),
+            [
+                "/usr/bin/flatpak",
+                "run",
+                "--branch=stable",
+                "--arch=x86_64",
+                "--command=gimp-2.10",
+                "--file-forwarding",
+                "org.gimp.GIMP",
+                "@@u",
+                "@@",
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_arguments() {
+        assert_eq!(
+            parse(r#"sh -c "cd ~/Games && ./start.sh""#),
+            ["sh", "-c", "cd ~/Games && ./start.sh"]
+        );
+        assert_eq!(parse(r#"app "" end"#), ["app", "", "end"]);
+        // Quotes in the middle of an argument are part of it, not separators.
+        assert_eq!(parse(r#"app --title="My App""#), ["app", "--title=My App"]);
+    }
+
+    #[test]
+    fn escapes_inside_quotes() {
+        // Escapes of the string type are resolved before the quoting rules, so
+        // desktop files double their backslashes.
+        assert_eq!(
+            parse(r#"sh -c "printf \\"%%s\\" \\$HOME""#),
+            ["sh", "-c", r#"printf "%s" $HOME"#]
+        );
+        assert_eq!(
+            parse(r#"wine "C:\\\\Program Files\\\\app.exe""#),
+            ["wine", r"C:\Program Files\app.exe"]
+        );
+        assert_eq!(parse(r#"app "\\`date\\`""#), ["app", "`date`"]);
+    }
+
+    #[test]
+    fn escapes_outside_quotes() {
+        assert_eq!(parse(r"app My\\ File"), ["app", "My File"]);
+        assert_eq!(parse(r"app --flag\sspaced"), ["app", "--flag", "spaced"]);
+    }
+
+    #[test]
+    fn percent_literal() {
+        assert_eq!(parse("printf 100%%"), ["printf", "100%"]);
+        assert_eq!(parse(r#"date "+%%H:%%M""#), ["date", "+%H:%M"]);
+    }
+
+    #[test]
+    fn icon_field_code() {
+        assert_eq!(parse("gimp %i"), ["gimp", "--icon", "example"]);
+        assert_eq!(parse("gimp %i %U"), ["gimp", "--icon", "example"]);
+        assert_eq!(parse("gimp --class=%i"), ["gimp", "--class=example"]);
+
+        let without_icon = parse_exec("gimp %i %U", &field_codes(None)).unwrap();
+        assert_eq!(without_icon, ["gimp"]);
+    }
+
+    #[test]
+    fn name_and_desktop_file_field_codes() {
+        assert_eq!(parse("app --name %c"), ["app", "--name", "Example App"]);
+        assert_eq!(parse(r#"app "%c""#), ["app", "Example App"]);
+        assert_eq!(
+            parse("app --desktop-file=%k"),
+            ["app", &format!("--desktop-file={DESKTOP_FILE}")]
+        );
+    }
+
+    #[test]
+    fn file_field_codes() {
+        let field_codes = FieldCodes {
+            file: Some("/home/user/My File.txt"),
+            ..field_codes(None)
+        };
+        let parse = |exec| parse_exec(exec, &field_codes).unwrap();
+
+        assert_eq!(parse("gedit %U"), ["gedit", "/home/user/My File.txt"]);
+        assert_eq!(
+            parse("app --open=%f"),
+            ["app", "--open=/home/user/My File.txt"]
+        );
+        assert_eq!(parse("app %f %F"), ["app", "/home/user/My File.txt"]);
+        // Applications without file codes are not given the file.
+        assert_eq!(parse("xterm"), ["xterm"]);
+
+        assert!(accepts_files("gedit %U"));
+        assert!(!accepts_files("xterm"));
+        assert!(!accepts_files("printf 100%%f"));
+    }
+
+    #[test]
+    fn deprecated_field_codes_are_dropped() {
+        assert_eq!(
+            parse("xterm %d %D %n %N %v %m -e top"),
+            ["xterm", "-e", "top"]
+        );
+    }
+
+    #[test]
+    fn malformed_exec_values() {
+        let field_codes = field_codes(None);
+        assert!(parse_exec(r#"app "unterminated"#, &field_codes).is_err());
+        assert!(parse_exec(r#"app "stray\\"#, &field_codes).is_err());
+        assert!(parse_exec(r"app trailing\\", &field_codes).is_err());
+        assert!(parse_exec("app 100%", &field_codes).is_err());
+    }
+
+    #[test]
+    fn program_of_exec() {
+        assert_eq!(
+            program("env GDK_BACKEND=x11 app %U").as_deref(),
+            Some("env")
+        );
+        assert_eq!(
+            program(r#""/opt/My App/app" %f"#).as_deref(),
+            Some("/opt/My App/app")
+        );
+        assert_eq!(program(""), None);
+        assert_eq!(program(r#"app "broken"#), None);
+    }
+}
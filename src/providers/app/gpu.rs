@@ -0,0 +1,42 @@
+use std::path::Path;
+
+/// Environment that makes an application render on the discrete GPU, honoring
+/// `PrefersNonDefaultGPU=true`. Empty when there is a single GPU to use.
+pub fn non_default_gpu_env() -> Vec<(String, String)> {
+    if gpu_count() < 2 {
+        return Vec::new();
+    }
+
+    let env: &[(&str, &str)] = if Path::new("/proc/driver/nvidia/version").exists() {
+        // NVIDIA PRIME render offload, for both OpenGL and Vulkan.
+        &[
+            ("__NV_PRIME_RENDER_OFFLOAD", "1"),
+            ("__GLX_VENDOR_LIBRARY_NAME", "nvidia"),
+            ("__VK_LAYER_NV_optimus", "NVIDIA_only"),
+        ]
+    } else {
+        &[("DRI_PRIME", "1")]
+    };
+
+    env.iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Counts the `/sys/class/drm/cardN` devices, leaving out their connectors.
+fn gpu_count() -> usize {
+    let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("card"))
+                .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .count()
+}
@@ -0,0 +1,138 @@
+/// Rank given to keys without a `[locale]` suffix, worse than any localized match.
+pub const UNLOCALIZED_RANK: u8 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    lang: String,
+    country: Option<String>,
+    modifier: Option<String>,
+}
+
+impl Locale {
+    /// Reads the messages locale from `LC_ALL`, `LC_MESSAGES` and `LANG`, in that order.
+    pub fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value))
+    }
+
+    /// Uses the user-defined locale when present, otherwise the environment one.
+    pub fn from_preference(preference: Option<&str>) -> Option<Self> {
+        preference.and_then(Self::parse).or_else(Self::from_env)
+    }
+
+    /// Parses a `lang_COUNTRY.ENCODING@MODIFIER` string. The encoding is ignored.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (rest, modifier) = match value.split_once('@') {
+            Some((rest, modifier)) => (rest, Some(modifier.to_string())),
+            None => (value, None),
+        };
+        let rest = rest.split_once('.').map_or(rest, |(rest, _encoding)| rest);
+        let (lang, country) = match rest.split_once('_') {
+            Some((lang, country)) => (lang, Some(country.to_string())),
+            None => (rest, None),
+        };
+
+        if lang.is_empty() || lang == "C" || lang == "POSIX" {
+            return None;
+        }
+
+        Some(Self {
+            lang: lang.to_string(),
+            country,
+            modifier,
+        })
+    }
+
+    /// Returns how well a key locale such as `Name[sr_RS@latin]` matches this
+    /// locale, lower is better, or `None` when the key must be ignored.
+    pub fn match_rank(&self, key_locale: &str) -> Option<u8> {
+        let key = Self::parse(key_locale)?;
+
+        if key.lang != self.lang {
+            return None;
+        }
+
+        let same_country = key.country == self.country;
+        let same_modifier = key.modifier == self.modifier;
+
+        match (key.country.is_some(), key.modifier.is_some()) {
+            (true, true) => (same_country && same_modifier).then_some(0),
+            (true, false) => same_country.then_some(1),
+            (false, true) => same_modifier.then_some(2),
+            (false, false) => Some(3),
+        }
+    }
+}
+
+/// A `localestring` value that keeps the best match seen so far.
+#[derive(Debug, Default)]
+pub struct Localized {
+    value: Option<String>,
+    rank: u8,
+}
+
+impl Localized {
+    pub fn offer(&mut self, value: &str, rank: u8) {
+        if self.value.is_none() || rank < self.rank {
+            self.value = Some(value.to_string());
+            self.rank = rank;
+        }
+    }
+
+    pub fn into_inner(self) -> Option<String> {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_locale_strings() {
+        let locale = Locale::parse("sr_RS.UTF-8@latin").unwrap();
+        assert_eq!(locale.lang, "sr");
+        assert_eq!(locale.country.as_deref(), Some("RS"));
+        assert_eq!(locale.modifier.as_deref(), Some("latin"));
+
+        let locale = Locale::parse("de").unwrap();
+        assert_eq!((locale.country, locale.modifier), (None, None));
+
+        assert_eq!(Locale::parse("C"), None);
+        assert_eq!(Locale::parse("POSIX"), None);
+        assert_eq!(Locale::parse(""), None);
+    }
+
+    #[test]
+    fn ranks_matches_by_specificity() {
+        let locale = Locale::parse("sr_RS@latin").unwrap();
+        assert_eq!(locale.match_rank("sr_RS@latin"), Some(0));
+        assert_eq!(locale.match_rank("sr_RS"), Some(1));
+        assert_eq!(locale.match_rank("sr@latin"), Some(2));
+        assert_eq!(locale.match_rank("sr"), Some(3));
+        assert_eq!(locale.match_rank("sr_ME"), None);
+        assert_eq!(locale.match_rank("sr@ijekavian"), None);
+        assert_eq!(locale.match_rank("de"), None);
+    }
+
+    #[test]
+    fn country_keys_need_the_same_country() {
+        let locale = Locale::parse("pt").unwrap();
+        assert_eq!(locale.match_rank("pt"), Some(3));
+        assert_eq!(locale.match_rank("pt_BR"), None);
+    }
+
+    #[test]
+    fn localized_keeps_the_best_offer() {
+        let mut value = Localized::default();
+        value.offer("Unlocalized", UNLOCALIZED_RANK);
+        value.offer("Language", 3);
+        value.offer("Later language", 3);
+        value.offer("Country", 1);
+        value.offer("Unlocalized again", UNLOCALIZED_RANK);
+        assert_eq!(value.into_inner().as_deref(), Some("Country"));
+    }
+}
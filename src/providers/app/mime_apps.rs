@@ -0,0 +1,540 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use iced::futures::StreamExt;
+
+use crate::providers::{
+    mime::{self, MIME_DATABASE},
+    spawn_with_new_session,
+    terminal::{resolve_terminal, wrap_in_terminal},
+};
+
+use super::{
+    application_dirs, desktop_entry::App, discover_apps, exec, exec::FieldCodes, locale::Locale,
+};
+
+const DEFAULT_APPLICATIONS: &str = "Default Applications";
+const ADDED_ASSOCIATIONS: &str = "Added Associations";
+const REMOVED_ASSOCIATIONS: &str = "Removed Associations";
+
+/// The last index built, with the locale preference it was built for.
+static SHARED: tokio::sync::Mutex<Option<(Option<String>, Arc<MimeApps>)>> =
+    tokio::sync::Mutex::const_new(None);
+
+/// The applications associated with each file type, following the freedesktop
+/// MIME Applications Associations specification: `mimeapps.list` files first,
+/// then the `MimeType` key of desktop entries.
+#[derive(Default)]
+pub struct MimeApps {
+    /// Installed applications taking files by desktop-file ID, including
+    /// `NoDisplay` ones.
+    apps: HashMap<String, App>,
+    /// Desktop-file IDs listing each type in their `MimeType` key, by precedence.
+    supported: HashMap<String, Vec<String>>,
+    /// Defaults of each type, from the most important `mimeapps.list` down.
+    defaults: HashMap<String, Vec<String>>,
+    added: HashMap<String, Vec<String>>,
+    removed: HashMap<String, HashSet<String>>,
+}
+
+/// An application able to open a given type.
+pub struct Handler<'a> {
+    pub app: &'a App,
+    pub is_default: bool,
+}
+
+impl MimeApps {
+    /// The index for the `locale` preference. It reads every desktop file, so
+    /// it is built once and shared until the associations change.
+    pub async fn shared(locale: Option<&str>) -> Arc<Self> {
+        let mut shared = SHARED.lock().await;
+        if let Some((shared_locale, mime_apps)) = shared.as_ref()
+            && shared_locale.as_deref() == locale
+        {
+            return mime_apps.clone();
+        }
+
+        let mime_apps = Arc::new(Self::load(Locale::from_preference(locale).as_ref()).await);
+        *shared = Some((locale.map(str::to_string), mime_apps.clone()));
+        mime_apps
+    }
+
+    async fn load(locale: Option<&Locale>) -> Self {
+        let mut mime_apps = Self::default();
+
+        // Hidden applications still open files, they are only left out of menus.
+        let mut app_stream = discover_apps(&application_dirs(), locale, false, true).await;
+        while let Some(app) = app_stream.next().await {
+            if !exec::accepts_files(&app.exec) {
+                continue;
+            }
+
+            for mime in &app.mime_types {
+                let mime = MIME_DATABASE.unalias(mime).to_string();
+                mime_apps
+                    .supported
+                    .entry(mime)
+                    .or_default()
+                    .push(app.id.clone());
+            }
+            mime_apps.apps.insert(app.id.clone(), app);
+        }
+
+        for path in mimeapps_lists() {
+            if let Ok(content) = tokio::fs::read_to_string(&path).await {
+                mime_apps.extend_from(&content);
+            }
+        }
+
+        mime_apps
+    }
+
+    /// Merges a `mimeapps.list` less important than the ones read before it.
+    /// Its removals only hide associations of the less important files.
+    fn extend_from(&mut self, content: &str) {
+        let mut removed = Vec::new();
+
+        for (group, mime, desktop_ids) in read_associations(content) {
+            let mime = MIME_DATABASE.unalias(mime).to_string();
+            match group {
+                DEFAULT_APPLICATIONS => {
+                    self.defaults.entry(mime).or_default().extend(desktop_ids);
+                }
+                ADDED_ASSOCIATIONS => {
+                    let hidden = self.removed.get(&mime);
+                    let added = self.added.entry(mime.clone()).or_default();
+                    for desktop_id in desktop_ids {
+                        if !hidden.is_some_and(|hidden| hidden.contains(&desktop_id))
+                            && !added.contains(&desktop_id)
+                        {
+                            added.push(desktop_id);
+                        }
+                    }
+                }
+                REMOVED_ASSOCIATIONS => removed.push((mime, desktop_ids)),
+                _ => {}
+            }
+        }
+
+        for (mime, desktop_ids) in removed {
+            self.removed.entry(mime).or_default().extend(desktop_ids);
+        }
+    }
+
+    /// The application opening `mime` by default: the first installed default
+    /// of the type or its closest parent, else its most preferred association.
+    pub fn default_handler(&self, mime: &str) -> Option<&App> {
+        let lineage = MIME_DATABASE.lineage(mime);
+
+        lineage
+            .iter()
+            .filter_map(|mime| self.defaults.get(mime))
+            .flatten()
+            .find_map(|desktop_id| self.apps.get(desktop_id))
+            .or_else(|| {
+                lineage
+                    .iter()
+                    .find_map(|mime| self.associations(mime).next())
+            })
+    }
+
+    /// Every application able to open `mime`, the default one first.
+    pub fn handlers(&self, mime: &str) -> Vec<Handler<'_>> {
+        let default_handler = self.default_handler(mime);
+        let mut seen = HashSet::new();
+
+        default_handler
+            .into_iter()
+            .chain(
+                MIME_DATABASE
+                    .lineage(mime)
+                    .iter()
+                    .flat_map(|mime| self.associations(mime).collect::<Vec<_>>()),
+            )
+            .filter(|app| seen.insert(app.id.as_str()))
+            .map(|app| Handler {
+                app,
+                is_default: default_handler.is_some_and(|default| default.id == app.id),
+            })
+            .collect()
+    }
+
+    /// Applications associated with exactly `mime`, added ones first.
+    fn associations(&self, mime: &str) -> impl Iterator<Item = &App> {
+        let removed = self.removed.get(mime);
+        let supported = self
+            .supported
+            .get(mime)
+            .into_iter()
+            .flatten()
+            .filter(move |desktop_id| {
+                !removed.is_some_and(|removed| removed.contains(*desktop_id))
+            });
+
+        self.added
+            .get(mime)
+            .into_iter()
+            .flatten()
+            .chain(supported)
+            .filter_map(|desktop_id| self.apps.get(desktop_id))
+    }
+}
+
+/// The command opening `file` with `app`, inside `terminal` when the
+/// application asks for one.
+pub fn open_command(
+    app: &App,
+    file: &Path,
+    terminal: Option<&[String]>,
+) -> anyhow::Result<Vec<String>> {
+    let file = file.to_string_lossy();
+    let field_codes = FieldCodes {
+        icon: app.icon.as_deref(),
+        name: &app.name,
+        desktop_file: &app.path,
+        file: Some(&file),
+    };
+
+    let command = exec::parse_exec(&app.exec, &field_codes)
+        .with_context(|| format!("invalid Exec key in {}", app.path.display()))?;
+
+    match terminal {
+        Some(terminal) if app.terminal => Ok(wrap_in_terminal(terminal, command)),
+        _ => Ok(command),
+    }
+}
+
+/// Opens `file` with the default application of its type, or `xdg-open` when
+/// there is none. The application starts in the directory of the file, unless
+/// its desktop entry sets one.
+pub async fn open_with_default(
+    file: PathBuf,
+    terminal: Option<String>,
+    locale: Option<String>,
+) -> anyhow::Result<()> {
+    let mime = mime::detect(file.clone()).await;
+    let mime_apps = MimeApps::shared(locale.as_deref()).await;
+    let Some(app) = mime_apps.default_handler(&mime) else {
+        // The desktop environment may know handlers we do not, such as portals.
+        tracing::info!(
+            mime,
+            "No application opens the file, falling back to xdg-open."
+        );
+        let mut command = process::Command::new("xdg-open");
+        command.arg(&file);
+        spawn_with_new_session(&mut command)
+            .with_context(|| format!("no application opens {mime} files, and xdg-open failed"))?;
+        return Ok(());
+    };
+
+    let terminal = resolve_terminal(terminal.as_deref());
+    let command = open_command(app, &file, terminal.as_deref())?;
+    let [binary, args @ ..] = command.as_slice() else {
+        anyhow::bail!("{} has an empty Exec key", app.id);
+    };
+
+    let mut command = process::Command::new(binary);
+    command.args(args);
+    if let Some(working_dir) = app.working_dir.as_deref().or(file.parent()) {
+        command.current_dir(working_dir);
+    }
+    tracing::info!(binary = ?binary, args = ?args, app = app.id, "Opening file with its default application.");
+
+    spawn_with_new_session(&mut command).with_context(|| format!("failed to spawn {binary}"))?;
+    Ok(())
+}
+
+/// Makes `desktop_id` the default application of `mime` in the user's
+/// `mimeapps.list`, keeping the rest of the file as is.
+pub async fn set_default(mime: String, desktop_id: String) -> Result<PathBuf, Arc<std::io::Error>> {
+    let config_home = xdg::BaseDirectories::new()
+        .get_config_home()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no config directory"))?;
+    tokio::fs::create_dir_all(&config_home).await?;
+
+    let path = config_home.join("mimeapps.list");
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(Arc::new(e)),
+    };
+
+    let tmp_path = path.with_extension("list.tmp");
+    let content = with_default(&content, &mime, &desktop_id);
+    tokio::fs::write(&tmp_path, content).await?;
+    tokio::fs::rename(&tmp_path, &path).await?;
+    SHARED.lock().await.take();
+
+    Ok(path)
+}
+
+/// `content` with the `[Default Applications]` entry of `mime` replaced,
+/// adding the group when missing.
+fn with_default(content: &str, mime: &str, desktop_id: &str) -> String {
+    let default_line = format!("{mime}={desktop_id};");
+    let mut lines: Vec<String> = Vec::new();
+    let mut group = None;
+    let mut is_written = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            if group == Some(DEFAULT_APPLICATIONS) && !is_written {
+                insert_before_blank_lines(&mut lines, default_line.clone());
+                is_written = true;
+            }
+            group = Some(if name == DEFAULT_APPLICATIONS {
+                DEFAULT_APPLICATIONS
+            } else {
+                ""
+            });
+        } else if group == Some(DEFAULT_APPLICATIONS)
+            && trimmed
+                .split_once('=')
+                .is_some_and(|(key, _)| key.trim() == mime)
+        {
+            if !is_written {
+                lines.push(default_line.clone());
+                is_written = true;
+            }
+            continue;
+        }
+
+        lines.push(line.to_string());
+    }
+
+    if !is_written {
+        if group == Some(DEFAULT_APPLICATIONS) {
+            insert_before_blank_lines(&mut lines, default_line);
+        } else {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{DEFAULT_APPLICATIONS}]"));
+            lines.push(default_line);
+        }
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+/// Keeps the blank lines separating a group from the next one after `line`.
+fn insert_before_blank_lines(lines: &mut Vec<String>, line: String) {
+    let blank_lines = lines
+        .iter()
+        .rev()
+        .take_while(|l| l.trim().is_empty())
+        .count();
+    lines.insert(lines.len() - blank_lines, line);
+}
+
+/// `(group, type, desktop-file IDs)` for every association of a `mimeapps.list`.
+fn read_associations(content: &str) -> Vec<(&str, &str, Vec<String>)> {
+    let mut group = "";
+    let mut associations = Vec::new();
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            group = name;
+            continue;
+        }
+
+        if let Some((mime, desktop_ids)) = line.split_once('=') {
+            let desktop_ids = desktop_ids
+                .split(';')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect();
+            associations.push((group, mime.trim(), desktop_ids));
+        }
+    }
+
+    associations
+}
+
+/// Every `mimeapps.list` by precedence, the ones of the current desktops
+/// before the generic one of each directory.
+fn mimeapps_lists() -> Vec<PathBuf> {
+    let xdg_dirs = xdg::BaseDirectories::new();
+    let desktops: Vec<String> = std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|desktop| !desktop.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let config_dirs = xdg_dirs
+        .get_config_home()
+        .into_iter()
+        .chain(xdg_dirs.get_config_dirs());
+    let data_dirs = xdg_dirs
+        .get_data_home()
+        .into_iter()
+        .chain(xdg_dirs.get_data_dirs())
+        .map(|dir| dir.join("applications"));
+
+    config_dirs
+        .chain(data_dirs)
+        .flat_map(|dir| {
+            desktops
+                .iter()
+                .map(|desktop| format!("{desktop}-mimeapps.list"))
+                .chain(["mimeapps.list".to_string()])
+                .map(move |file_name| dir.join(file_name))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_LIST: &str = "\
+[Default Applications]
+text/plain=editor.desktop;
+
+[Added Associations]
+text/plain=editor.desktop;
+
+[Removed Associations]
+text/plain=viewer.desktop;editor.desktop;
+";
+
+    const SYSTEM_LIST: &str = "\
+[Default Applications]
+text/plain=ide.desktop;
+
+[Added Associations]
+text/plain=viewer.desktop;ide.desktop;editor.desktop;
+";
+
+    fn mime_apps(lists: &[&str], apps: &[(&str, &[&str])]) -> MimeApps {
+        let mut mime_apps = MimeApps::default();
+        for (id, mime_types) in apps {
+            for mime in *mime_types {
+                let supported = mime_apps.supported.entry(mime.to_string()).or_default();
+                supported.push(id.to_string());
+            }
+            let app = App {
+                id: id.to_string(),
+                ..App::default()
+            };
+            mime_apps.apps.insert(id.to_string(), app);
+        }
+
+        for list in lists {
+            mime_apps.extend_from(list);
+        }
+        mime_apps
+    }
+
+    fn handler_ids(mime_apps: &MimeApps, mime: &str) -> Vec<(String, bool)> {
+        mime_apps
+            .handlers(mime)
+            .into_iter()
+            .map(|handler| (handler.app.id.clone(), handler.is_default))
+            .collect()
+    }
+
+    #[test]
+    fn more_important_lists_win() {
+        let apps: &[(&str, &[&str])] = &[
+            ("editor.desktop", &[]),
+            ("viewer.desktop", &["text/plain"]),
+            ("ide.desktop", &[]),
+        ];
+        let mime_apps = mime_apps(&[USER_LIST, SYSTEM_LIST], apps);
+
+        // Removals hide the associations of less important lists and of
+        // `MimeType` keys, but not the ones added next to them.
+        assert_eq!(
+            handler_ids(&mime_apps, "text/plain"),
+            [
+                ("editor.desktop".to_string(), true),
+                ("ide.desktop".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn defaults_skip_missing_applications() {
+        let apps: &[(&str, &[&str])] = &[("ide.desktop", &[]), ("viewer.desktop", &["image/png"])];
+        let mime_apps = mime_apps(&[USER_LIST, SYSTEM_LIST], apps);
+
+        assert_eq!(
+            mime_apps
+                .default_handler("text/plain")
+                .map(|app| app.id.as_str()),
+            Some("ide.desktop")
+        );
+        // Without a default, the most preferred association opens the type.
+        assert_eq!(
+            mime_apps
+                .default_handler("image/png")
+                .map(|app| app.id.as_str()),
+            Some("viewer.desktop")
+        );
+        assert!(mime_apps.default_handler("video/mp4").is_none());
+    }
+
+    #[test]
+    fn with_default_replaces_the_entry_of_the_type() {
+        let content = "\
+# Written by hand
+[Default Applications]
+text/plain=old.desktop;
+image/png=viewer.desktop;
+
+[Added Associations]
+text/plain=old.desktop;
+";
+
+        assert_eq!(
+            with_default(content, "text/plain", "editor.desktop"),
+            "\
+# Written by hand
+[Default Applications]
+text/plain=editor.desktop;
+image/png=viewer.desktop;
+
+[Added Associations]
+text/plain=old.desktop;
+"
+        );
+    }
+
+    #[test]
+    fn with_default_adds_the_entry_or_the_group() {
+        let content = "[Default Applications]\nimage/png=viewer.desktop;\n\n[Added Associations]\n";
+        assert_eq!(
+            with_default(content, "text/plain", "editor.desktop"),
+            "[Default Applications]\nimage/png=viewer.desktop;\ntext/plain=editor.desktop;\n\n\
+             [Added Associations]\n"
+        );
+
+        let content = "[Added Associations]\ntext/plain=old.desktop;\n";
+        assert_eq!(
+            with_default(content, "text/plain", "editor.desktop"),
+            "[Added Associations]\ntext/plain=old.desktop;\n\n\
+             [Default Applications]\ntext/plain=editor.desktop;\n"
+        );
+
+        assert_eq!(
+            with_default("", "text/plain", "editor.desktop"),
+            "[Default Applications]\ntext/plain=editor.desktop;\n"
+        );
+    }
+}
@@ -0,0 +1,607 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    pin::pin,
+    process,
+    sync::{Arc, Mutex},
+};
+
+use iced::futures::future::{self, Either};
+use iced::futures::{Stream, StreamExt};
+use iced::widget::image;
+use iced::{Task, futures::SinkExt};
+use iced::{futures, window};
+
+use crate::preferences::{AppPreferences, CustomEntry};
+use crate::providers::icon::{
+    cache::{IconCache, IconKey},
+    service::IconService,
+    theme::IconLookup,
+};
+use crate::providers::terminal::{resolve_terminal, wrap_in_terminal};
+use crate::providers::{ScanRequest, Scanner};
+use crate::ui::entry::{EntryIcon, SearchTerms};
+use crate::{launcher::Message, ui::icon::APPLICATION_DEFAULT};
+
+use super::{Entry, Provider, spawn_with_new_session};
+
+mod desktop_entry;
+mod exec;
+mod gpu;
+mod locale;
+pub mod mime_apps;
+mod watcher;
+
+use desktop_entry::{App, desktop_file_id, parse_desktop_entry};
+use exec::FieldCodes;
+use locale::Locale;
+use watcher::DesktopFileWatcher;
+
+/// Custom entries are identified by their name, kept apart from desktop-file IDs.
+const CUSTOM_ENTRY_ID_PREFIX: &str = "custom:";
+
+#[derive(Debug, Clone, Copy)]
+pub struct AppProvider;
+
+impl Provider for AppProvider {
+    fn scan(request: ScanRequest) -> impl Stream<Item = Message> {
+        let icon_size = request.preferences.theme.launchpad.entry.icon_size;
+        let mut icon_scale = request.icon_scale;
+        let scan_batch_size = request.preferences.scan_batch_size;
+        let locale = Locale::from_preference(request.preferences.locale.as_deref());
+        let terminal = resolve_terminal(request.preferences.terminal.as_deref());
+        let show_broken_apps = request.preferences.show_broken_apps;
+        let icon_theme = request.preferences.icon_theme.clone();
+        let app_preferences = request.preferences.apps.clone();
+        let custom_entries = request.preferences.custom_entries.clone();
+        iced::stream::channel(100, async move |output| {
+            let icon_lookup = IconLookup::reload(icon_theme.as_deref());
+            let icon_cache = IconCache::open()
+                .await
+                .inspect_err(|e| tracing::warn!(error = %e, "Icon cache unavailable"))
+                .ok();
+            let icon_service = IconService::spawn(
+                icon_lookup.clone(),
+                icon_cache.clone(),
+                APPLICATION_DEFAULT.clone(),
+                output.clone(),
+            );
+            let mut entry_builder = EntryBuilder {
+                terminal,
+                app_preferences,
+                gpu_env: gpu::non_default_gpu_env(),
+                icon_lookup,
+                icon_cache,
+                icon_service,
+                icon_size,
+                icon_scale: *icon_scale.borrow_and_update(),
+                icon_names: Mutex::default(),
+            };
+            let app_dirs = application_dirs();
+            // Entry IDs produced by each desktop-file ID, to know what to replace
+            // or remove when its file changes.
+            let mut entry_ids: HashMap<String, Vec<String>> = HashMap::new();
+
+            // Watching before the scan catches the files changing during it.
+            let mut watcher = DesktopFileWatcher::new(&app_dirs)
+                .inspect_err(|e| {
+                    tracing::warn!(error = %e, "Cannot watch desktop files, the list will not be reloaded.");
+                })
+                .ok();
+
+            let mut app_stream =
+                discover_apps(&app_dirs, locale.as_ref(), show_broken_apps, false).await;
+            let mut scanner = Scanner::new(output.clone(), scan_batch_size);
+            scanner.start().await;
+            while let Some(app) = app_stream.next().await {
+                let entries = entry_builder.build(&app).await;
+                entry_ids.insert(app.id, entries.iter().map(|e| e.id.clone()).collect());
+                for entry in entries {
+                    scanner.load(entry).await;
+                }
+            }
+            // Custom entries are not tracked in `entry_ids`, so reloads leave them alone.
+            for custom_entry in &custom_entries {
+                if let Some(entry) = entry_builder.build_custom(custom_entry).await {
+                    scanner.load(entry).await;
+                }
+            }
+            scanner.finish().await;
+
+            loop {
+                let changes = match future::select(
+                    pin!(next_changes(watcher.as_mut())),
+                    pin!(icon_scale.changed()),
+                )
+                .await
+                {
+                    Either::Left((Some(changes), _)) => Some(changes),
+                    Either::Right((Ok(()), _)) => None,
+                    Either::Left((None, _)) | Either::Right((Err(_), _)) => break,
+                };
+
+                let Some(changes) = changes else {
+                    let scale = *icon_scale.borrow_and_update();
+                    tracing::debug!(scale, "Icon scale changed, requesting icons again.");
+                    let cached = entry_builder.rescale(scale).await;
+                    let _ = output.clone().send(Message::IconsResolved(cached)).await;
+                    continue;
+                };
+
+                let changed_ids: HashSet<String> = changes
+                    .paths
+                    .iter()
+                    .filter_map(|path| {
+                        app_dirs
+                            .iter()
+                            .find_map(|app_dir| desktop_file_id(app_dir, path))
+                    })
+                    .collect();
+                tracing::debug!(changed = ?changed_ids, "Desktop files changed, reloading.");
+
+                // Rediscovering is cheap and settles precedence between search paths,
+                // but only the apps that actually changed get their entries rebuilt.
+                let mut stale_ids: HashSet<String> = entry_ids.keys().cloned().collect();
+                let mut updated = Vec::new();
+                let mut removed = Vec::new();
+                let mut app_stream =
+                    discover_apps(&app_dirs, locale.as_ref(), show_broken_apps, false).await;
+
+                while let Some(app) = app_stream.next().await {
+                    let is_known = stale_ids.remove(&app.id);
+                    if is_known && !changes.all_changed && !changed_ids.contains(&app.id) {
+                        continue;
+                    }
+
+                    let entries = entry_builder.build(&app).await;
+                    let new_ids: Vec<String> = entries.iter().map(|e| e.id.clone()).collect();
+                    if let Some(old_ids) = entry_ids.insert(app.id, new_ids.clone()) {
+                        removed.extend(old_ids.into_iter().filter(|id| !new_ids.contains(id)));
+                    }
+                    updated.extend(entries);
+                }
+
+                for id in stale_ids {
+                    removed.extend(entry_ids.remove(&id).unwrap_or_default());
+                }
+
+                entry_builder.forget_icons(&removed);
+                scanner.remove(removed).await;
+                scanner.update(updated).await;
+            }
+        })
+    }
+
+    fn launch(entry: &Entry) -> Task<Message> {
+        let [binary, args @ ..] = entry.command.as_slice() else {
+            tracing::warn!("Launch failed: provided entry resulted in an empty command.");
+            return Task::none();
+        };
+
+        let mut command = process::Command::new(binary);
+        command.args(args).envs(entry.env.iter().cloned());
+        if let Some(working_dir) = &entry.working_dir {
+            command.current_dir(working_dir);
+        }
+        tracing::info!(binary = ?binary, args = ?args, "Attempting to launch detached process.");
+
+        if let Err(e) = spawn_with_new_session(&mut command) {
+            tracing::error!(error = %e, binary = ?binary, "Failed to spawn process.");
+            return Task::none();
+        }
+
+        tracing::info!(binary = ?binary, "Process launched successfully.");
+        window::latest().and_then(window::close)
+    }
+}
+
+/// Turns an application into its launcher entries, shared by the initial scan
+/// and the reloads triggered by desktop file changes.
+struct EntryBuilder {
+    terminal: Option<Vec<String>>,
+    app_preferences: HashMap<String, AppPreferences>,
+    gpu_env: Vec<(String, String)>,
+    icon_lookup: Arc<IconLookup>,
+    icon_cache: Option<IconCache>,
+    icon_service: IconService,
+    icon_size: u32,
+    icon_scale: u32,
+    /// Icon name of every entry, to request them again at another scale.
+    icon_names: Mutex<HashMap<String, String>>,
+}
+
+impl EntryBuilder {
+    async fn build(&self, app: &App) -> Vec<Entry> {
+        let secondary = app.comment.clone().or_else(|| app.generic_name.clone());
+        let search_terms = search_terms(app);
+        let mut entries = Vec::new();
+
+        for launchable in app.launchables() {
+            let field_codes = FieldCodes {
+                icon: launchable.icon.as_deref(),
+                name: &app.name,
+                desktop_file: &app.path,
+                file: None,
+            };
+            // Settings of the application also apply to its actions.
+            let preferences = self
+                .app_preferences
+                .get(&launchable.id)
+                .or_else(|| self.app_preferences.get(&app.id));
+            let command = match exec::parse_exec(&launchable.exec, &field_codes) {
+                Ok(command) => apply_preferences(command, preferences),
+                Err(e) => {
+                    tracing::warn!(error = %e, path = ?app.path, "Skipping desktop entry with an invalid Exec key.");
+                    continue;
+                }
+            };
+            let command = match &self.terminal {
+                Some(terminal) if app.terminal => wrap_in_terminal(terminal, command),
+                _ => command,
+            };
+
+            let mut env = if app.prefers_non_default_gpu {
+                self.gpu_env.clone()
+            } else {
+                Vec::new()
+            };
+            if let Some(preferences) = preferences {
+                env.extend(preferences.env.clone());
+            }
+
+            let id = launchable.id;
+            let icon = match launchable.icon {
+                Some(icon_name) => self.icon(&id, icon_name).await,
+                None => EntryIcon::Handle(APPLICATION_DEFAULT.clone()),
+            };
+
+            let entry = Entry::new(id, launchable.name, secondary.clone(), icon)
+                .command(command)
+                .search_terms(search_terms.clone())
+                .working_dir(app.working_dir.clone())
+                .env(env);
+            entries.push(entry);
+        }
+
+        entries
+    }
+
+    async fn build_custom(&self, custom_entry: &CustomEntry) -> Option<Entry> {
+        let field_codes = FieldCodes {
+            icon: custom_entry.icon.as_deref(),
+            name: &custom_entry.name,
+            desktop_file: Path::new(""),
+            file: None,
+        };
+        let command = match exec::parse_exec(&custom_entry.command, &field_codes) {
+            Ok(command) if !command.is_empty() => command,
+            Ok(_) => {
+                tracing::warn!(
+                    name = custom_entry.name,
+                    "Skipping custom entry with an empty command."
+                );
+                return None;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, name = custom_entry.name, "Skipping custom entry with an invalid command.");
+                return None;
+            }
+        };
+
+        let id = format!("{CUSTOM_ENTRY_ID_PREFIX}{}", custom_entry.name);
+        let icon = match &custom_entry.icon {
+            Some(icon_name) => self.icon(&id, icon_name.clone()).await,
+            None => EntryIcon::Handle(APPLICATION_DEFAULT.clone()),
+        };
+
+        let entry =
+            Entry::new(id, &custom_entry.name, custom_entry.comment.clone(), icon).command(command);
+        Some(entry)
+    }
+
+    /// Requests every icon again at `icon_scale`. Returns the cached ones, the
+    /// icon service sends the others.
+    async fn rescale(&mut self, icon_scale: u32) -> Vec<(String, image::Handle)> {
+        self.icon_scale = icon_scale;
+        let icon_names =
+            std::mem::take(self.icon_names.get_mut().unwrap_or_else(|e| e.into_inner()));
+
+        let mut cached = Vec::new();
+        for (id, icon_name) in icon_names {
+            if let EntryIcon::Handle(handle) = self.icon(&id, icon_name).await {
+                cached.push((id, handle));
+            }
+        }
+
+        cached
+    }
+
+    fn forget_icons(&mut self, ids: &[String]) {
+        let icon_names = self.icon_names.get_mut().unwrap_or_else(|e| e.into_inner());
+        for id in ids {
+            icon_names.remove(id);
+        }
+    }
+
+    /// Returns the cached icon, or a placeholder while the icon service resolves it.
+    async fn icon(&self, id: &str, icon_name: String) -> EntryIcon {
+        self.icon_names
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id.to_string(), icon_name.clone());
+
+        let key = IconKey {
+            theme: self.icon_lookup.theme_name().to_string(),
+            name: icon_name.clone(),
+            size: self.icon_size,
+            scale: self.icon_scale,
+            theme_fingerprint: self.icon_lookup.fingerprint(),
+        };
+        let cached = match &self.icon_cache {
+            Some(icon_cache) => icon_cache.get(&key).await,
+            None => None,
+        };
+
+        match cached {
+            Some(icon) => EntryIcon::Handle(icon.into_handle()),
+            None => {
+                self.icon_service.request(id.to_string(), key);
+                EntryIcon::Lazy(icon_name)
+            }
+        }
+    }
+}
+
+/// The next desktop file changes, never ready without a watcher.
+async fn next_changes(watcher: Option<&mut DesktopFileWatcher>) -> Option<watcher::Changes> {
+    match watcher {
+        Some(watcher) => watcher.next_changes().await,
+        None => future::pending().await,
+    }
+}
+
+/// Runs `command` through the configured wrapper and appends the configured arguments.
+fn apply_preferences(command: Vec<String>, preferences: Option<&AppPreferences>) -> Vec<String> {
+    let Some(preferences) = preferences else {
+        return command;
+    };
+
+    preferences
+        .wrapper
+        .iter()
+        .cloned()
+        .chain(command)
+        .chain(preferences.args.iter().cloned())
+        .collect()
+}
+
+/// The fields besides the name an app is found by.
+fn search_terms(app: &App) -> SearchTerms {
+    SearchTerms {
+        generic_name: app.generic_name.clone(),
+        keywords: app.keywords.clone(),
+        categories: app.categories.clone(),
+    }
+}
+
+/// The entry ID and `Exec` value of everything the installed applications can
+/// launch, hidden ones included, by precedence.
+pub async fn installed_launchables() -> Vec<(String, String)> {
+    let mut launchables = Vec::new();
+    let mut app_stream = discover_apps(&application_dirs(), None, true, true).await;
+    while let Some(app) = app_stream.next().await {
+        launchables.extend(
+            app.launchables()
+                .map(|launchable| (launchable.id, launchable.exec)),
+        );
+    }
+
+    launchables
+}
+
+/// Every `applications` directory, ordered by precedence.
+fn application_dirs() -> Vec<PathBuf> {
+    let xdg_dirs = xdg::BaseDirectories::new();
+    let mut search_paths = xdg_dirs.get_data_dirs();
+    search_paths.insert(0, xdg_dirs.get_data_home().unwrap_or_default());
+
+    search_paths
+        .into_iter()
+        .map(|path| path.join("applications"))
+        .collect()
+}
+
+/// Streams the applications of `app_dirs`, by precedence. Unless asked for, the
+/// ones hidden by `NoDisplay`, `OnlyShowIn` or `NotShowIn` are left out, and so
+/// are those whose program is missing.
+async fn discover_apps(
+    app_dirs: &[PathBuf],
+    locale: Option<&Locale>,
+    show_broken_apps: bool,
+    show_hidden_apps: bool,
+) -> futures::channel::mpsc::Receiver<App> {
+    let (tx, rx) = futures::channel::mpsc::channel(100);
+    let current_desktop = std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(":")
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    let mut seen_ids = HashSet::new();
+
+    for app_dir in app_dirs {
+        for file_path in find_desktop_files(app_dir).await {
+            let Some(desktop_file_id) = desktop_file_id(app_dir, &file_path) else {
+                continue;
+            };
+
+            // Search paths are ordered by precedence, so the first file claims the ID
+            // even when it is hidden or invalid, shadowing lower-priority copies.
+            if !seen_ids.insert(desktop_file_id.clone()) {
+                tracing::debug!(path = ?file_path, "Skipping shadowed desktop file.");
+                continue;
+            }
+
+            if let Ok(content) = tokio::fs::read_to_string(&file_path).await
+                && let Some(mut app) = parse_desktop_entry(&content, &current_desktop, locale)
+            {
+                if !show_hidden_apps && app.no_display {
+                    tracing::debug!(path = ?file_path, "Skipping hidden desktop file.");
+                    continue;
+                }
+
+                if !show_broken_apps && !app.is_installed() {
+                    tracing::debug!(path = ?file_path, "Skipping desktop file of a missing program.");
+                    continue;
+                }
+
+                app.id = desktop_file_id;
+                app.path = file_path;
+                let _ = tx.clone().send(app).await;
+            }
+        }
+    }
+
+    rx
+}
+
+/// Lists every `.desktop` file below `app_dir`. Symlinks are followed, and each
+/// directory is visited once by its canonical path so symlink loops terminate.
+async fn find_desktop_files(app_dir: &Path) -> Vec<PathBuf> {
+    let mut desktop_files = Vec::new();
+    let mut visited_dirs = HashSet::new();
+    let mut pending_dirs = vec![app_dir.to_path_buf()];
+
+    while let Some(dir) = pending_dirs.pop() {
+        let Ok(canonical_dir) = tokio::fs::canonicalize(&dir).await else {
+            continue;
+        };
+
+        if !visited_dirs.insert(canonical_dir) {
+            tracing::debug!(path = ?dir, "Skipping already visited directory.");
+            continue;
+        }
+
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            continue;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let Ok(metadata) = tokio::fs::metadata(&path).await else {
+                continue;
+            };
+
+            if metadata.is_dir() {
+                pending_dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "desktop") {
+                desktop_files.push(path);
+            }
+        }
+    }
+
+    desktop_files
+}
+
+#[cfg(test)]
+mod tests {
+    use fuzzy_matcher::skim::SkimMatcherV2;
+
+    use super::*;
+    use crate::{
+        preferences::Preferences, providers::usage::UsageHistory, ui::entry::EntryRegistry,
+    };
+
+    const FIREFOX: &str = "[Desktop Entry]
+Type=Application
+Name=Firefox
+GenericName=Web Browser
+Comment=Browse the World Wide Web
+Keywords=Internet;WWW;Browser;Web;Explorer;
+Categories=Network;WebBrowser;
+Exec=firefox %u
+";
+
+    const QUTEBROWSER: &str = "[Desktop Entry]
+Type=Application
+Name=qutebrowser
+GenericName=Web Browser
+Comment=A keyboard-driven, vim-like browser based on Python and Qt
+Categories=Network;WebBrowser;
+Exec=qutebrowser %u
+";
+
+    const GEDIT: &str = "[Desktop Entry]
+Type=Application
+Name=Text Editor
+Comment=Edit text files
+Keywords=Text;Editor;Plaintext;Write;gedit;
+Categories=GNOME;GTK;Utility;TextEditor;
+Exec=gedit %U
+";
+
+    const SYSTEM_MONITOR: &str = "[Desktop Entry]
+Type=Application
+Name=System Monitor
+Comment=View current processes and monitor system state
+Keywords=Monitor;System;Process;CPU;Memory;Network;History;Usage;Performance;Task;Manager;Activity;
+Categories=GNOME;GTK;System;Monitor;
+Exec=gnome-system-monitor
+";
+
+    fn registry() -> EntryRegistry {
+        let mut registry = EntryRegistry::default();
+        registry.extend(
+            [
+                ("firefox.desktop", FIREFOX),
+                ("org.qutebrowser.qutebrowser.desktop", QUTEBROWSER),
+                ("org.gnome.gedit.desktop", GEDIT),
+                ("gnome-system-monitor.desktop", SYSTEM_MONITOR),
+            ]
+            .map(|(id, content)| {
+                let app = parse_desktop_entry(content, &[], None).unwrap();
+                Entry::new(
+                    id,
+                    &app.name,
+                    app.comment.clone(),
+                    EntryIcon::Lazy(id.to_string()),
+                )
+                .search_terms(search_terms(&app))
+            }),
+        );
+        registry
+    }
+
+    fn ranked_ids(registry: &mut EntryRegistry, pattern: &str) -> Vec<String> {
+        registry.sort_by_rank(
+            &Preferences::default(),
+            &SkimMatcherV2::default(),
+            pattern,
+            &UsageHistory::default(),
+            false,
+        );
+        registry.iter_visible().map(|e| e.id.clone()).collect()
+    }
+
+    #[test]
+    fn apps_are_found_by_generic_name_keywords_and_categories() {
+        let mut registry = registry();
+
+        assert_eq!(
+            ranked_ids(&mut registry, "browser"),
+            ["org.qutebrowser.qutebrowser.desktop", "firefox.desktop"]
+        );
+        assert_eq!(
+            ranked_ids(&mut registry, "editor"),
+            ["org.gnome.gedit.desktop"]
+        );
+        // A category hit ranks below the keyword hit of another app.
+        assert_eq!(
+            ranked_ids(&mut registry, "network"),
+            [
+                "gnome-system-monitor.desktop",
+                "firefox.desktop",
+                "org.qutebrowser.qutebrowser.desktop"
+            ]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use iced::futures::StreamExt;
+use inotify::{EventMask, EventOwned, EventStream, Inotify, WatchDescriptor, WatchMask};
+
+/// Events arriving this close to each other are reported as a single change,
+/// since package managers touch many files at once.
+const SETTLE_DELAY: Duration = Duration::from_millis(250);
+const APPLICATIONS_DIR: &str = "applications";
+
+/// Desktop files touched since the last batch. When events were lost, or a
+/// whole directory moved, every file must be considered changed instead.
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub paths: HashSet<PathBuf>,
+    pub all_changed: bool,
+}
+
+/// Watches every `applications` directory, and the directories below them,
+/// for desktop files being added, rewritten, moved or removed. The data
+/// directories holding them are watched too, for `applications` directories
+/// created later.
+pub struct DesktopFileWatcher {
+    events: EventStream<Vec<u8>>,
+    watched_dirs: HashMap<WatchDescriptor, PathBuf>,
+    /// The watch of each directory by its canonical path, so a directory
+    /// reachable through symlinks keeps the path it was first watched as.
+    canonical_dirs: HashMap<PathBuf, WatchDescriptor>,
+    data_dirs: HashMap<WatchDescriptor, PathBuf>,
+}
+
+impl DesktopFileWatcher {
+    pub fn new(app_dirs: &[PathBuf]) -> std::io::Result<Self> {
+        let events = Inotify::init()?.into_event_stream(vec![0; 4096])?;
+        let mut watcher = Self {
+            events,
+            watched_dirs: HashMap::new(),
+            canonical_dirs: HashMap::new(),
+            data_dirs: HashMap::new(),
+        };
+
+        for app_dir in app_dirs {
+            if let Some(data_dir) = app_dir.parent() {
+                watcher.watch_data_dir(data_dir);
+            }
+            watcher.watch_tree(app_dir);
+        }
+
+        Ok(watcher)
+    }
+
+    /// Waits for the next batch of changes. Returns `None` once the watch is closed.
+    pub async fn next_changes(&mut self) -> Option<Changes> {
+        let mut changes = Changes::default();
+        let first = self.events.next().await?;
+        self.record(first, &mut changes);
+
+        while let Ok(Some(event)) = tokio::time::timeout(SETTLE_DELAY, self.events.next()).await {
+            self.record(event, &mut changes);
+        }
+
+        Some(changes)
+    }
+
+    fn record(&mut self, event: std::io::Result<EventOwned>, changes: &mut Changes) {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to read desktop file changes.");
+                return;
+            }
+        };
+
+        if event.mask.contains(EventMask::Q_OVERFLOW) {
+            tracing::warn!("Desktop file watch overflowed, some changes were missed.");
+            changes.all_changed = true;
+            return;
+        }
+
+        if event.mask.contains(EventMask::IGNORED) {
+            self.watched_dirs.remove(&event.wd);
+            self.canonical_dirs.retain(|_, wd| *wd != event.wd);
+            self.data_dirs.remove(&event.wd);
+            return;
+        }
+
+        if let Some(data_dir) = self.data_dirs.get(&event.wd) {
+            if event.mask.contains(EventMask::ISDIR)
+                && event.name.is_some_and(|name| name == APPLICATIONS_DIR)
+            {
+                let app_dir = data_dir.join(APPLICATIONS_DIR);
+                self.watch_tree(&app_dir);
+                changes.all_changed = true;
+            }
+            return;
+        }
+
+        let (Some(dir), Some(name)) = (self.watched_dirs.get(&event.wd), event.name) else {
+            return;
+        };
+        let path = dir.join(name);
+
+        if event.mask.contains(EventMask::ISDIR) {
+            if event
+                .mask
+                .intersects(EventMask::CREATE | EventMask::MOVED_TO)
+            {
+                self.watch_tree(&path);
+            }
+            // Files of a moved or removed directory are not reported one by one.
+            changes.all_changed = true;
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            changes.paths.insert(path);
+        }
+    }
+
+    /// Adds a watch on `data_dir` reporting the directories created in it.
+    fn watch_data_dir(&mut self, data_dir: &Path) {
+        let mask = WatchMask::CREATE | WatchMask::MOVED_TO | WatchMask::ONLYDIR;
+        match self.events.watches().add(data_dir, mask) {
+            Ok(wd) => {
+                self.data_dirs.insert(wd, data_dir.to_path_buf());
+            }
+            Err(e) => {
+                tracing::debug!(error = %e, path = ?data_dir, "Cannot watch data directory.");
+            }
+        }
+    }
+
+    /// Adds a watch on `root` and every directory below it, following symlinks
+    /// like the scan does while watching each directory once, across trees too.
+    fn watch_tree(&mut self, root: &Path) {
+        let mask = WatchMask::CREATE
+            | WatchMask::CLOSE_WRITE
+            | WatchMask::DELETE
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO
+            | WatchMask::ONLYDIR;
+        let mut pending_dirs = vec![root.to_path_buf()];
+
+        while let Some(dir) = pending_dirs.pop() {
+            let Ok(canonical_dir) = dir.canonicalize() else {
+                continue;
+            };
+
+            if self.canonical_dirs.contains_key(&canonical_dir) {
+                tracing::debug!(path = ?dir, "Skipping already watched directory.");
+                continue;
+            }
+
+            match self.events.watches().add(&canonical_dir, mask) {
+                Ok(wd) => {
+                    self.canonical_dirs.insert(canonical_dir, wd.clone());
+                    self.watched_dirs.entry(wd).or_insert_with(|| dir.clone());
+                }
+                Err(e) => {
+                    tracing::debug!(error = %e, path = ?dir, "Cannot watch applications directory.");
+                    continue;
+                }
+            }
+
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+
+            pending_dirs.extend(
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_dir()),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symlinked_app_dir_keeps_the_first_watched_path() {
+        let root = std::env::temp_dir().join(format!(
+            "{}-watcher-{}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        let app_dir = root.join("share/applications");
+        std::fs::create_dir_all(app_dir.join("kde")).unwrap();
+        std::fs::create_dir_all(root.join("alias")).unwrap();
+        let alias_dir = root.join("alias/applications");
+        std::os::unix::fs::symlink(&app_dir, &alias_dir).unwrap();
+
+        let watcher = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async { DesktopFileWatcher::new(&[app_dir.clone(), alias_dir]) })
+            .unwrap();
+        let mut watched: Vec<&PathBuf> = watcher.watched_dirs.values().collect();
+        watched.sort();
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(watched, [&app_dir, &app_dir.join("kde")]);
+    }
+}
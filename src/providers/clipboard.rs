@@ -71,10 +71,10 @@ impl Provider for ClipboardProvider {
             return Task::none();
         };
 
-        if let Some(mut stdin) = child.stdin.take() {
-            if let Err(e) = stdin.write_all(entry.id.as_bytes()) {
-                tracing::error!("Failed to write to wl-copy stdin: {}", e);
-            }
+        if let Some(mut stdin) = child.stdin.take()
+            && let Err(e) = stdin.write_all(entry.id.as_bytes())
+        {
+            tracing::error!("Failed to write to wl-copy stdin: {}", e);
         }
 
         window::latest().and_then(window::close)
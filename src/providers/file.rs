@@ -1,147 +1,621 @@
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     process,
+    sync::{
+        Arc, LazyLock, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
 use iced::{Task, futures::Stream, widget::image, window};
+use ignore::{WalkBuilder, WalkParallel, WalkState, overrides::OverrideBuilder};
 
 use crate::{
     launcher::Message,
-    providers::{ScanRequest, Scanner},
+    preferences::{FilePreferences, SortBy},
+    providers::{
+        ScanRequest, Scanner,
+        app::mime_apps::{Handler, MimeApps, open_command},
+        icon::{RasterIcon, load_raster_icon, theme::IconLookup},
+        mime::{self, MIME_DATABASE},
+        terminal::resolve_terminal,
+    },
     ui::{
         entry::{Entry, EntryIcon},
         icon::{
-            APPLICATION_DEFAULT, AUDIO_GENERIC, FOLDER_DEFAULT, FONT_GENERIC, IMAGE_GENERIC,
-            MODEL_GENERIC, MULTIPART_GENERIC, TEXT_GENERIC, VIDEO_GENERIC,
+            ADDON_GENERIC, APPLICATION_DEFAULT, APPLICATION_GENERIC, AUDIO_GENERIC, FOLDER_DEFAULT,
+            FONT_GENERIC, HTML_GENERIC, IMAGE_GENERIC, MODEL_GENERIC, MULTIPART_GENERIC,
+            SCRIPT_GENERIC, SHAREDLIB_GENERIC, SYMLINK_DEFAULT, TEXT_GENERIC, VIDEO_GENERIC,
         },
     },
 };
 
 use super::{Provider, spawn_with_new_session};
 
+/// Matches sent at once by recursive searches, which can find thousands of them.
+const SEARCH_BATCH_SIZE: usize = 256;
+/// Longest time a match waits before being shown.
+const SEARCH_FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+/// Short queries match most of a tree. Past this many results, refining the
+/// query is more useful than the rest of the walk.
+const MAX_SEARCH_RESULTS: usize = 5000;
+
 #[derive(Debug, Clone, Copy)]
 pub struct FileProvider;
 
 impl Provider for FileProvider {
     fn scan(request: ScanRequest) -> impl Stream<Item = Message> {
-        let icon_size = request.preferences.theme.launchpad.entry.icon_size;
         iced::stream::channel(100, async move |output| {
-            let mut scanner = Scanner::new(output, request.preferences.scan_batch_size);
-            scanner.start().await;
-            if let Some(parent_directory) = request.path.parent() {
-                let parent_entry = Entry::new(
-                    parent_directory.to_string_lossy(),
-                    "..",
-                    Some(parent_directory.to_string_lossy()),
-                    EntryIcon::Handle(get_icon_from_mimetype(parent_directory, icon_size)),
-                );
-                scanner.load(parent_entry).await;
+            if let Some(file) = request.open_with.clone() {
+                let scanner = Scanner::new(output, request.preferences.scan_batch_size);
+                return list_handlers(scanner, &request, &file).await;
             }
 
-            let mut child_directories = match tokio::fs::read_dir(&request.path).await {
-                Ok(dir) => dir,
-                Err(e) => return scanner.error(anyhow::anyhow!(e)).await,
-            };
-
-            loop {
-                let child_dir = match child_directories.next_entry().await {
-                    Ok(Some(entry)) => entry,
-                    Ok(None) => break,
-                    Err(e) => return scanner.error(anyhow::anyhow!(e)).await,
-                };
-
-                let path = child_dir.path();
-                let main_display = path.file_name().and_then(|s| s.to_str()).unwrap_or("..");
-                let child_entry = Entry::new(
-                    path.to_string_lossy(),
-                    main_display,
-                    Some(path.to_string_lossy()),
-                    EntryIcon::Handle(get_icon_from_mimetype(&path, icon_size)),
-                );
-                scanner.load(child_entry).await;
+            match request.query.clone() {
+                Some(query) => {
+                    let scanner = Scanner::new(output, SEARCH_BATCH_SIZE);
+                    search_recursively(scanner, &request, query).await;
+                }
+                None => {
+                    let scanner = Scanner::new(output, request.preferences.scan_batch_size);
+                    list_directory(scanner, &request).await;
+                }
             }
-
-            scanner.finish().await;
         })
     }
 
     fn launch(entry: &Entry) -> Task<Message> {
-        let path = PathBuf::from(&entry.id);
+        // Entries listed by "Open with" carry the command opening the file.
+        if let [binary, args @ ..] = entry.command.as_slice() {
+            let mut command = process::Command::new(binary);
+            command.args(args);
+            if let Some(working_dir) = &entry.working_dir {
+                command.current_dir(working_dir);
+            }
+            tracing::info!(binary = ?binary, args = ?args, "Attempting to launch detached process.");
+
+            if let Err(e) = spawn_with_new_session(&mut command) {
+                tracing::error!(error = %e, binary = ?binary, "Failed to spawn process.");
+                return Task::none();
+            }
+
+            tracing::info!(binary = ?binary, "Process launched successfully.");
+            return window::latest().and_then(window::close);
+        }
 
+        let path = PathBuf::from(&entry.id);
         if path.is_dir() {
-            return Task::done(Message::ChangePath(path));
+            Task::done(Message::ChangePath(path))
+        } else {
+            Task::done(Message::OpenFile(path))
+        }
+    }
+}
+
+/// Lists the applications able to open `file`, its default application first.
+async fn list_handlers(mut scanner: Scanner, request: &ScanRequest, file: &Path) {
+    scanner.start().await;
+    // Detecting the type loads the database before the applications need it.
+    let mime = mime::detect(file.to_path_buf()).await;
+    let mime_apps = MimeApps::shared(request.preferences.locale.as_deref()).await;
+
+    let request = request.clone();
+    let file = file.to_path_buf();
+    let entries = tokio::task::spawn_blocking(move || {
+        let file_icons = FileIcons::new(&request);
+        let terminal = resolve_terminal(request.preferences.terminal.as_deref());
+        handler_entries(&mime_apps, &mime, &file, terminal.as_deref(), &file_icons)
+    })
+    .await;
+
+    match entries {
+        Ok(entries) => {
+            for entry in entries {
+                scanner.load(entry).await;
+            }
+            scanner.finish().await;
         }
-        let mut command = process::Command::new("xdg-open");
-        command.arg(&path);
-        tracing::info!(binary = ?command.get_program(), arg = ?path, "Attempting to launch detached process.");
+        Err(e) => scanner.error(anyhow::anyhow!(e)).await,
+    }
+}
+
+/// The entries of the applications able to open `file`, whose icons are read
+/// from the disk.
+fn handler_entries(
+    mime_apps: &MimeApps,
+    mime: &str,
+    file: &Path,
+    terminal: Option<&[String]>,
+    file_icons: &FileIcons,
+) -> Vec<Entry> {
+    let mut entries = Vec::new();
+
+    for Handler { app, is_default } in mime_apps.handlers(mime) {
+        let command = match open_command(app, file, terminal) {
+            Ok(command) => command,
+            Err(e) => {
+                tracing::warn!(error = %e, app = app.id, "Skipping application unable to open the file.");
+                continue;
+            }
+        };
 
-        if let Err(e) = spawn_with_new_session(&mut command) {
-            tracing::error!(error = %e, binary = ?command.get_program(), "Failed to spawn process.");
+        let secondary = if is_default {
+            Some("Default application".to_string())
         } else {
-            tracing::info!(binary = ?command.get_program(), "Process launched successfully.");
+            app.comment.clone()
+        };
+        let icon = app
+            .icon
+            .as_deref()
+            .and_then(|icon_name| file_icons.named(icon_name))
+            .unwrap_or_else(|| APPLICATION_DEFAULT.clone());
+        let working_dir = app
+            .working_dir
+            .clone()
+            .or_else(|| file.parent().map(Path::to_path_buf));
+
+        let entry = Entry::new(&app.id, &app.name, secondary, EntryIcon::Handle(icon))
+            .command(command)
+            .working_dir(working_dir);
+        entries.push(entry);
+    }
+
+    entries
+}
+
+async fn list_directory(mut scanner: Scanner, request: &ScanRequest) {
+    scanner.start().await;
+    let preferences = &request.preferences.providers.file;
+    let mut child_directories = match tokio::fs::read_dir(&request.path).await {
+        Ok(dir) => dir,
+        Err(e) => return scanner.error(anyhow::anyhow!(e)).await,
+    };
+
+    // Sorting needs every child, so they are only sent once the directory is read.
+    let mut children = Vec::new();
+    let needs_metadata = matches!(preferences.sort_by, SortBy::Mtime | SortBy::Size);
+    loop {
+        let child_dir = match child_directories.next_entry().await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => break,
+            Err(e) => return scanner.error(anyhow::anyhow!(e)).await,
+        };
+
+        let name = child_dir.file_name().to_string_lossy().into_owned();
+        if !preferences.show_hidden && name.starts_with('.') {
+            continue;
+        }
+
+        let path = child_dir.path();
+        let mut child = Child {
+            path,
+            name,
+            is_dir: false,
+            modified: None,
+            size: 0,
+        };
+
+        // Symlinks are sorted like what they point at.
+        if needs_metadata {
+            let metadata = match tokio::fs::metadata(&child.path).await {
+                Ok(metadata) => Some(metadata),
+                Err(_) => child_dir.metadata().await.ok(),
+            };
+            child.is_dir = metadata.as_ref().is_some_and(|m| m.is_dir());
+            child.modified = metadata.as_ref().and_then(|m| m.modified().ok());
+            child.size = metadata.as_ref().map_or(0, |m| m.len());
+        } else if preferences.directories_first {
+            // The file type comes with the directory entry, only symlinks need a stat.
+            child.is_dir = match child_dir.file_type().await {
+                Ok(file_type) if file_type.is_symlink() => tokio::fs::metadata(&child.path)
+                    .await
+                    .is_ok_and(|m| m.is_dir()),
+                Ok(file_type) => file_type.is_dir(),
+                Err(_) => false,
+            };
         }
 
-        window::latest().and_then(window::close)
+        children.push(child);
+    }
+
+    sort_children(&mut children, preferences);
+
+    let parent = request
+        .path
+        .parent()
+        .map(|parent| (parent.to_path_buf(), "..".to_string()));
+    let mut listing = parent
+        .into_iter()
+        .chain(children.into_iter().map(|child| (child.path, child.name)))
+        .peekable();
+    // Detecting types and loading icons reads from the disk, so every batch
+    // is built on the blocking thread pool, and the icons with the first one.
+    let file_icons = {
+        let request = request.clone();
+        Arc::new(LazyLock::new(move || FileIcons::new(&request)))
+    };
+    while listing.peek().is_some() {
+        let batch: Vec<(PathBuf, String)> = listing
+            .by_ref()
+            .take(request.preferences.scan_batch_size.max(1))
+            .collect();
+        let file_icons = file_icons.clone();
+        let working_dir = request.path.clone();
+        let entries = tokio::task::spawn_blocking(move || {
+            batch
+                .into_iter()
+                .map(|(path, name)| {
+                    Entry::new(
+                        path.to_string_lossy(),
+                        name,
+                        Some(path.to_string_lossy()),
+                        EntryIcon::Handle(file_icons.icon(&path)),
+                    )
+                    .working_dir(Some(working_dir.clone()))
+                })
+                .collect::<Vec<_>>()
+        })
+        .await;
+
+        match entries {
+            Ok(entries) => {
+                for entry in entries {
+                    scanner.load(entry).await;
+                }
+            }
+            Err(e) => return scanner.error(anyhow::anyhow!(e)).await,
+        }
     }
+
+    scanner.finish().await;
 }
 
-fn get_icon_from_mimetype(path: &Path, _size: u32) -> image::Handle {
-    if path.is_dir() {
-        return FOLDER_DEFAULT.clone();
+/// A directory child, with what listings are sorted by.
+struct Child {
+    path: PathBuf,
+    name: String,
+    is_dir: bool,
+    modified: Option<SystemTime>,
+    size: u64,
+}
+
+/// Sorts `children` following the listing preferences. Directories stay first
+/// when reversed, only the order within each group changes.
+fn sort_children(children: &mut [Child], preferences: &FilePreferences) {
+    let by_name = |a: &Child, b: &Child| {
+        a.name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.name.cmp(&b.name))
+    };
+    let extension = |child: &Child| {
+        Path::new(&child.name)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+    };
+
+    children.sort_by(|a, b| {
+        let order = match preferences.sort_by {
+            SortBy::Name => by_name(a, b),
+            SortBy::Mtime => b.modified.cmp(&a.modified).then_with(|| by_name(a, b)),
+            SortBy::Size => b.size.cmp(&a.size).then_with(|| by_name(a, b)),
+            SortBy::Extension => extension(a).cmp(&extension(b)).then_with(|| by_name(a, b)),
+        };
+        let order = if preferences.reverse {
+            order.reverse()
+        } else {
+            order
+        };
+
+        if preferences.directories_first {
+            b.is_dir.cmp(&a.is_dir).then(order)
+        } else {
+            order
+        }
+    });
+}
+
+/// Walks the tree below `request.path` in parallel, sending every file and
+/// directory whose name matches `query` as soon as it is found. The walk stops
+/// once the scan is dropped, which happens when the query or the path changes.
+async fn search_recursively(mut scanner: Scanner, request: &ScanRequest, query: String) {
+    scanner.start().await;
+    let walker = match build_walker(&request.path, &request.preferences.providers.file) {
+        Ok(walker) => walker,
+        Err(e) => return scanner.error(e).await,
+    };
+
+    let request = request.clone();
+    let (sender, mut matches) = tokio::sync::mpsc::channel(SEARCH_BATCH_SIZE);
+    let walk = tokio::task::spawn_blocking(move || {
+        let file_icons = FileIcons::new(&request);
+        let found = AtomicUsize::new(0);
+        walker.run(|| {
+            let sender = sender.clone();
+            let matcher = SkimMatcherV2::default();
+            let query = &query;
+            let found = &found;
+            let file_icons = &file_icons;
+            Box::new(move |result| {
+                if sender.is_closed() || found.load(Ordering::Relaxed) >= MAX_SEARCH_RESULTS {
+                    return WalkState::Quit;
+                }
+
+                let dir_entry = match result {
+                    Ok(dir_entry) => dir_entry,
+                    Err(e) => {
+                        tracing::debug!(error = %e, "Skipping unreadable path while searching.");
+                        return WalkState::Continue;
+                    }
+                };
+
+                let name = dir_entry.file_name().to_string_lossy();
+                if dir_entry.depth() == 0 || matcher.fuzzy_match(&name, query).is_none() {
+                    return WalkState::Continue;
+                }
+
+                found.fetch_add(1, Ordering::Relaxed);
+                let path = dir_entry.path();
+                let entry = Entry::new(
+                    path.to_string_lossy(),
+                    name,
+                    Some(path.to_string_lossy()),
+                    EntryIcon::Handle(file_icons.icon(path)),
+                )
+                .working_dir(path.parent().map(Path::to_path_buf));
+
+                match sender.blocking_send(entry) {
+                    Ok(()) => WalkState::Continue,
+                    Err(_) => WalkState::Quit,
+                }
+            })
+        });
+    });
+
+    // Matches are flushed on a timer too, since they may trickle in slower than
+    // a batch fills up.
+    let mut last_flush = Instant::now();
+    loop {
+        match tokio::time::timeout(SEARCH_FLUSH_INTERVAL, matches.recv()).await {
+            Ok(Some(entry)) => scanner.load(entry).await,
+            Ok(None) => break,
+            Err(_elapsed) => {}
+        }
+
+        if last_flush.elapsed() >= SEARCH_FLUSH_INTERVAL {
+            scanner.flush().await;
+            last_flush = Instant::now();
+        }
     }
 
-    let file_extension = path
-        .to_string_lossy()
-        .rsplit_once('.')
-        .map(|(_, ext)| ext.to_lowercase())
-        .unwrap_or_default();
+    if let Err(e) = walk.await {
+        return scanner.error(anyhow::anyhow!(e)).await;
+    }
+
+    scanner.finish().await;
+}
 
-    let mimetype = MimeType::get_type_from_extension(&file_extension);
+/// A parallel walk honoring `.gitignore` and `.ignore` files, even outside of
+/// git repositories, along with the `exclude` preference.
+fn build_walker(root: &Path, preferences: &FilePreferences) -> anyhow::Result<WalkParallel> {
+    let mut overrides = OverrideBuilder::new(root);
+    for pattern in &preferences.exclude {
+        // Override globs include what they match, so they are negated to exclude it.
+        overrides.add(&format!("!{pattern}"))?;
+    }
+
+    let walker = WalkBuilder::new(root)
+        .require_git(false)
+        .hidden(!preferences.show_hidden)
+        .max_depth(Some(preferences.max_depth))
+        .overrides(overrides.build()?)
+        .build_parallel();
 
-    // TODO: Feature to override or add new mimetype icons.
-    // load_raster_icon(&mimetype.get_icon_from_type(), size).unwrap_or_else(default_icon)
-    mimetype.get_icon_from_type()
+    Ok(walker)
 }
 
-#[derive(Debug)]
-pub enum MimeType {
-    Text,
-    Application,
-    Image,
-    Audio,
-    Video,
-    Font,
-    Multipart,
-    Model,
-    Unknown,
+/// Resolves the icon of every file type once per scan, from the icon theme,
+/// falling back to the built-in icons.
+struct FileIcons {
+    icon_lookup: Arc<IconLookup>,
+    size: u32,
+    scale: u32,
+    resolved: Mutex<HashMap<Vec<String>, image::Handle>>,
 }
 
-impl MimeType {
-    fn get_type_from_extension(ext: &str) -> MimeType {
-        match ext {
-            "txt" | "md" | "html" | "css" | "csv" => MimeType::Text,
-            "json" | "pdf" | "zip" | "wasm" | "xml" => MimeType::Application,
-            "jpg" | "jpeg" | "png" | "gif" | "webp" | "svg" => MimeType::Image,
-            "mp3" | "wav" | "ogg" | "m4a" => MimeType::Audio,
-            "mp4" | "webm" | "avi" | "mov" => MimeType::Video,
-            "ttf" | "otf" | "woff" | "woff2" => MimeType::Font,
-            "mime" | "mhtml" => MimeType::Multipart,
-            "obj" | "stl" | "glb" | "gltf" | "3ds" => MimeType::Model,
-            _ => MimeType::Unknown,
+impl FileIcons {
+    fn new(request: &ScanRequest) -> Self {
+        Self {
+            icon_lookup: IconLookup::shared(request.preferences.icon_theme.as_deref()),
+            size: request.preferences.theme.launchpad.entry.icon_size,
+            scale: *request.icon_scale.borrow(),
+            resolved: Mutex::new(HashMap::new()),
         }
     }
 
-    fn get_icon_from_type(&self) -> image::Handle {
-        match self {
-            MimeType::Text => TEXT_GENERIC.clone(),
-            MimeType::Application => APPLICATION_DEFAULT.clone(),
-            MimeType::Image => IMAGE_GENERIC.clone(),
-            MimeType::Audio => AUDIO_GENERIC.clone(),
-            MimeType::Video => VIDEO_GENERIC.clone(),
-            MimeType::Font => FONT_GENERIC.clone(),
-            MimeType::Multipart => MULTIPART_GENERIC.clone(),
-            MimeType::Model => MODEL_GENERIC.clone(),
-            MimeType::Unknown => TEXT_GENERIC.clone(),
+    fn icon(&self, path: &Path) -> image::Handle {
+        let mime = MIME_DATABASE.detect(path);
+        let mut icon_names = MIME_DATABASE.icon_names(&mime);
+        if mime == mime::DIRECTORY {
+            let folder_names = USER_DIR_ICONS
+                .get(path)
+                .into_iter()
+                .chain(["folder"].iter());
+            icon_names.splice(0..0, folder_names.map(|name| name.to_string()));
+        }
+
+        let resolved = self.resolved.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(handle) = resolved.get(&icon_names) {
+            return handle.clone();
         }
+        // Not held while resolving, at worst a type is resolved twice.
+        drop(resolved);
+
+        let handle = icon_names
+            .iter()
+            .find_map(|name| {
+                let path = self.icon_lookup.find_icon(name, self.size, self.scale)?;
+                load_raster_icon(&path, self.size * self.scale)
+            })
+            .map(RasterIcon::into_handle)
+            .unwrap_or_else(|| builtin_icon(&icon_names));
+
+        let mut resolved = self.resolved.lock().unwrap_or_else(|e| e.into_inner());
+        resolved.insert(icon_names, handle.clone());
+        handle
+    }
+
+    /// The themed icon called `icon_name`, such as the icon of an application.
+    fn named(&self, icon_name: &str) -> Option<image::Handle> {
+        let path = self
+            .icon_lookup
+            .find_icon(icon_name, self.size, self.scale)?;
+        load_raster_icon(&path, self.size * self.scale).map(RasterIcon::into_handle)
+    }
+}
+
+/// The built-in icon matching the most specific of `icon_names`.
+fn builtin_icon(icon_names: &[String]) -> image::Handle {
+    icon_names
+        .iter()
+        .find_map(|name| match name.as_str() {
+            "folder" | "inode-directory" => Some(FOLDER_DEFAULT.clone()),
+            "inode-symlink" => Some(SYMLINK_DEFAULT.clone()),
+            "text-html" => Some(HTML_GENERIC.clone()),
+            "text-x-script" => Some(SCRIPT_GENERIC.clone()),
+            "text-x-generic" => Some(TEXT_GENERIC.clone()),
+            "image-x-generic" => Some(IMAGE_GENERIC.clone()),
+            "audio-x-generic" => Some(AUDIO_GENERIC.clone()),
+            "video-x-generic" => Some(VIDEO_GENERIC.clone()),
+            "font-x-generic" => Some(FONT_GENERIC.clone()),
+            "model-x-generic" => Some(MODEL_GENERIC.clone()),
+            "package-x-generic" | "multipart-x-generic" => Some(MULTIPART_GENERIC.clone()),
+            "application-x-executable" => Some(APPLICATION_DEFAULT.clone()),
+            "application-x-sharedlib" => Some(SHAREDLIB_GENERIC.clone()),
+            "application-x-addon" => Some(ADDON_GENERIC.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| APPLICATION_GENERIC.clone())
+}
+
+/// Icons of the home directory and the XDG user directories, by path.
+static USER_DIR_ICONS: LazyLock<HashMap<PathBuf, &'static str>> = LazyLock::new(|| {
+    let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else {
+        return HashMap::new();
+    };
+
+    let user_dirs = xdg::BaseDirectories::new()
+        .get_config_home()
+        .and_then(|config_home| std::fs::read_to_string(config_home.join("user-dirs.dirs")).ok())
+        .unwrap_or_default();
+
+    let mut icons: HashMap<PathBuf, &'static str> = user_dirs
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .filter_map(|(key, value)| {
+            let icon_name = match key.trim() {
+                "XDG_DESKTOP_DIR" => "user-desktop",
+                "XDG_DOCUMENTS_DIR" => "folder-documents",
+                "XDG_DOWNLOAD_DIR" => "folder-download",
+                "XDG_MUSIC_DIR" => "folder-music",
+                "XDG_PICTURES_DIR" => "folder-pictures",
+                "XDG_PUBLICSHARE_DIR" => "folder-publicshare",
+                "XDG_TEMPLATES_DIR" => "folder-templates",
+                "XDG_VIDEOS_DIR" => "folder-videos",
+                _ => return None,
+            };
+            let value = value.trim().trim_matches('"');
+            let path = match value.strip_prefix("$HOME") {
+                Some(relative) => home.join(relative.trim_start_matches('/')),
+                None => PathBuf::from(value),
+            };
+            // Unused directories point at the home directory itself.
+            (path != home).then_some((path, icon_name))
+        })
+        .collect();
+
+    icons.insert(home, "user-home");
+    icons
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn child(name: &str, is_dir: bool, modified_secs: u64, size: u64) -> Child {
+        Child {
+            path: PathBuf::from("/tmp").join(name),
+            name: name.to_string(),
+            is_dir,
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(modified_secs)),
+            size,
+        }
+    }
+
+    fn sorted_names(preferences: &FilePreferences) -> Vec<String> {
+        let mut children = vec![
+            child("notes.txt", false, 30, 10),
+            child("Archive.zip", false, 10, 300),
+            child("src", true, 20, 0),
+            child("build.rs", false, 40, 20),
+            child("Docs", true, 50, 0),
+        ];
+        sort_children(&mut children, preferences);
+        children.into_iter().map(|child| child.name).collect()
+    }
+
+    #[test]
+    fn sorts_by_name_with_directories_first() {
+        let preferences = FilePreferences::default();
+        assert_eq!(
+            sorted_names(&preferences),
+            ["Docs", "src", "Archive.zip", "build.rs", "notes.txt"]
+        );
+
+        let preferences = FilePreferences {
+            directories_first: false,
+            ..FilePreferences::default()
+        };
+        assert_eq!(
+            sorted_names(&preferences),
+            ["Archive.zip", "build.rs", "Docs", "notes.txt", "src"]
+        );
+    }
+
+    #[test]
+    fn reverse_keeps_directories_first() {
+        let preferences = FilePreferences {
+            reverse: true,
+            ..FilePreferences::default()
+        };
+        assert_eq!(
+            sorted_names(&preferences),
+            ["src", "Docs", "notes.txt", "build.rs", "Archive.zip"]
+        );
+    }
+
+    #[test]
+    fn sorts_by_mtime_size_and_extension() {
+        let by = |sort_by| FilePreferences {
+            sort_by,
+            directories_first: false,
+            ..FilePreferences::default()
+        };
+
+        assert_eq!(
+            sorted_names(&by(SortBy::Mtime)),
+            ["Docs", "build.rs", "notes.txt", "src", "Archive.zip"]
+        );
+        assert_eq!(
+            sorted_names(&by(SortBy::Size)),
+            ["Archive.zip", "build.rs", "notes.txt", "Docs", "src"]
+        );
+        assert_eq!(
+            sorted_names(&by(SortBy::Extension)),
+            ["Docs", "src", "build.rs", "notes.txt", "Archive.zip"]
+        );
     }
 }
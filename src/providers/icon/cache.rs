@@ -0,0 +1,162 @@
+use std::{
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use sqlx::{
+    SqlitePool,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+};
+
+use super::RasterIcon;
+
+/// Bumped when the schema changes, which drops the cached icons.
+const SCHEMA_VERSION: i64 = 1;
+
+/// Identifies a resolved icon. Entries are only valid while the source
+/// file keeps the modification time recorded alongside them, and the theme
+/// directories their fingerprint.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IconKey {
+    pub theme: String,
+    pub name: String,
+    pub size: u32,
+    pub scale: u32,
+    /// See [`IconLookup::fingerprint`](super::theme::IconLookup::fingerprint).
+    pub theme_fingerprint: u64,
+}
+
+/// Persists resolved icon paths and rasterized pixels in
+/// `$XDG_CACHE_HOME/lucien/icons.db` so later scans skip the theme lookup.
+#[derive(Debug, Clone)]
+pub struct IconCache {
+    pool: SqlitePool,
+}
+
+impl IconCache {
+    pub async fn open() -> anyhow::Result<Self> {
+        let cache_file = xdg::BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"))
+            .place_cache_file("icons.db")?;
+        let conn_options = SqliteConnectOptions::new()
+            .filename(&cache_file)
+            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
+            .synchronous(sqlx::sqlite::SqliteSynchronous::Normal)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_with(conn_options)
+            .await?;
+
+        migrate(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    pub async fn get(&self, key: &IconKey) -> Option<RasterIcon> {
+        let (path, mtime, width, height, pixels) =
+            sqlx::query_as::<_, (String, i64, Option<u32>, Option<u32>, Option<Vec<u8>>)>(
+                "SELECT path, mtime, width, height, pixels FROM icons \
+            WHERE theme = ? AND name = ? AND size = ? AND scale = ? AND fingerprint = ?",
+            )
+            .bind(&key.theme)
+            .bind(&key.name)
+            .bind(key.size)
+            .bind(key.scale)
+            .bind(key.theme_fingerprint as i64)
+            .fetch_optional(&self.pool)
+            .await
+            .inspect_err(|e| tracing::warn!(error = %e, "Failed to read the icon cache"))
+            .ok()??;
+
+        let path = PathBuf::from(path);
+        if modified_time(&path)? != mtime {
+            return None;
+        }
+
+        match (width, height, pixels) {
+            (Some(width), Some(height), Some(pixels)) => Some(RasterIcon::Rgba {
+                width,
+                height,
+                pixels,
+            }),
+            _ => Some(RasterIcon::File(path)),
+        }
+    }
+
+    pub async fn insert(&self, key: &IconKey, source: &Path, icon: &RasterIcon) {
+        let Some(mtime) = modified_time(source) else {
+            return;
+        };
+        let (width, height, pixels) = match icon {
+            RasterIcon::File(_) => (None, None, None),
+            RasterIcon::Rgba {
+                width,
+                height,
+                pixels,
+            } => (Some(*width), Some(*height), Some(pixels.as_slice())),
+        };
+
+        let result = sqlx::query(
+            "INSERT OR REPLACE INTO icons \
+            (theme, name, size, scale, fingerprint, path, mtime, width, height, pixels) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&key.theme)
+        .bind(&key.name)
+        .bind(key.size)
+        .bind(key.scale)
+        .bind(key.theme_fingerprint as i64)
+        .bind(source.to_string_lossy())
+        .bind(mtime)
+        .bind(width)
+        .bind(height)
+        .bind(pixels)
+        .execute(&self.pool)
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!(error = %e, icon = key.name, "Failed to write the icon cache");
+        }
+    }
+}
+
+fn modified_time(path: &Path) -> Option<i64> {
+    let modified = path.metadata().ok()?.modified().ok()?;
+    let since_epoch = modified.duration_since(UNIX_EPOCH).ok()?;
+    i64::try_from(since_epoch.as_nanos()).ok()
+}
+
+async fn migrate(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+        .fetch_one(pool)
+        .await?;
+    if version != SCHEMA_VERSION {
+        sqlx::query("DROP TABLE IF EXISTS icons")
+            .execute(pool)
+            .await?;
+        sqlx::query(&format!("PRAGMA user_version = {SCHEMA_VERSION}"))
+            .execute(pool)
+            .await?;
+    }
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS icons (
+            theme TEXT NOT NULL,
+            name TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            scale INTEGER NOT NULL,
+            fingerprint INTEGER NOT NULL,
+            path TEXT NOT NULL,
+            mtime INTEGER NOT NULL,
+            width INTEGER,
+            height INTEGER,
+            pixels BLOB,
+            PRIMARY KEY (theme, name, size, scale)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
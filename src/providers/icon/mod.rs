@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use iced::widget::image;
+use resvg::{tiny_skia, usvg};
+
+pub mod cache;
+pub mod service;
+pub mod theme;
+
+/// An icon ready to be displayed: raster files are loaded by path, while
+/// vector icons are rendered into RGBA pixels.
+pub enum RasterIcon {
+    File(PathBuf),
+    Rgba {
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    },
+}
+
+impl RasterIcon {
+    pub fn into_handle(self) -> image::Handle {
+        match self {
+            RasterIcon::File(path) => image::Handle::from_path(path),
+            RasterIcon::Rgba {
+                width,
+                height,
+                pixels,
+            } => image::Handle::from_rgba(width, height, pixels),
+        }
+    }
+}
+
+/// Loads the icon at `path`, rendering vector icons at `size` physical pixels
+/// so they stay sharp on scaled displays.
+pub fn load_raster_icon(path: &Path, size: u32) -> Option<RasterIcon> {
+    let extension = path.extension()?.to_str()?;
+
+    match extension {
+        "svg" => {
+            let pixmap = rasterize_svg(path, size)?;
+            Some(RasterIcon::Rgba {
+                width: size,
+                height: size,
+                pixels: pixmap.take(),
+            })
+        }
+        "png" => Some(RasterIcon::File(path.to_path_buf())),
+        _ => None,
+    }
+}
+
+fn rasterize_svg(path: &Path, size: u32) -> Option<tiny_skia::Pixmap> {
+    let svg_data = std::fs::read(path).ok()?;
+    let opts = usvg::Options::default();
+    let tree = usvg::Tree::from_data(&svg_data, &opts).ok()?;
+
+    let mut pixmap = tiny_skia::Pixmap::new(size, size)?;
+    let transform = tiny_skia::Transform::from_scale(
+        size as f32 / tree.size().width(),
+        size as f32 / tree.size().height(),
+    );
+
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    Some(pixmap)
+}
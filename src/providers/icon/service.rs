@@ -0,0 +1,181 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use iced::futures::{SinkExt, channel::mpsc::Sender as FuturesSender};
+use iced::widget::image;
+use tokio::sync::mpsc;
+
+use super::{
+    cache::{IconCache, IconKey},
+    load_raster_icon,
+    theme::IconLookup,
+};
+use crate::launcher::Message;
+
+const WORKER_COUNT: usize = 4;
+/// How long resolved icons are held back so they reach the UI as one message.
+const BATCH_WINDOW: Duration = Duration::from_millis(16);
+
+type Waiters = Arc<Mutex<HashMap<IconKey, Vec<String>>>>;
+
+/// Resolves icons on a fixed set of workers. Entries sharing an icon name,
+/// size and scale wait on a single lookup, and results are sent in batches.
+pub struct IconService {
+    jobs: mpsc::UnboundedSender<IconKey>,
+    waiters: Waiters,
+}
+
+impl IconService {
+    pub fn spawn(
+        icon_lookup: Arc<IconLookup>,
+        icon_cache: Option<IconCache>,
+        fallback: image::Handle,
+        output: FuturesSender<Message>,
+    ) -> Self {
+        let (jobs_sender, jobs_receiver) = mpsc::unbounded_channel();
+        let (results_sender, results_receiver) = mpsc::unbounded_channel();
+        let jobs_receiver = Arc::new(tokio::sync::Mutex::new(jobs_receiver));
+        let waiters = Waiters::default();
+
+        for _ in 0..WORKER_COUNT {
+            tokio::spawn(worker(
+                jobs_receiver.clone(),
+                icon_lookup.clone(),
+                icon_cache.clone(),
+                fallback.clone(),
+                results_sender.clone(),
+            ));
+        }
+        tokio::spawn(collect(results_receiver, waiters.clone(), output));
+
+        Self {
+            jobs: jobs_sender,
+            waiters,
+        }
+    }
+
+    pub fn request(&self, id: String, key: IconKey) {
+        let mut waiters = self.waiters.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(ids) = waiters.get_mut(&key) {
+            ids.push(id);
+            return;
+        }
+
+        waiters.insert(key.clone(), vec![id]);
+        let _ = self.jobs.send(key);
+    }
+}
+
+async fn worker(
+    jobs: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<IconKey>>>,
+    icon_lookup: Arc<IconLookup>,
+    icon_cache: Option<IconCache>,
+    fallback: image::Handle,
+    results: mpsc::UnboundedSender<(IconKey, image::Handle)>,
+) {
+    loop {
+        let Some(key) = jobs.lock().await.recv().await else {
+            break;
+        };
+
+        let handle = resolve_icon(&key, &icon_lookup, icon_cache.as_ref())
+            .await
+            .unwrap_or_else(|| fallback.clone());
+
+        if results.send((key, handle)).is_err() {
+            break;
+        }
+    }
+}
+
+async fn resolve_icon(
+    key: &IconKey,
+    icon_lookup: &Arc<IconLookup>,
+    icon_cache: Option<&IconCache>,
+) -> Option<image::Handle> {
+    let (icon, path) = tokio::task::spawn_blocking({
+        let icon_lookup = icon_lookup.clone();
+        let key = key.clone();
+        move || {
+            let path = icon_lookup.find_icon(&key.name, key.size, key.scale)?;
+            let icon = load_raster_icon(&path, key.size * key.scale)?;
+            Some((icon, path))
+        }
+    })
+    .await
+    .ok()??;
+
+    if let Some(icon_cache) = icon_cache {
+        icon_cache.insert(key, &path, &icon).await;
+    }
+
+    Some(icon.into_handle())
+}
+
+async fn collect(
+    mut results: mpsc::UnboundedReceiver<(IconKey, image::Handle)>,
+    waiters: Waiters,
+    mut output: FuturesSender<Message>,
+) {
+    while let Some(first) = results.recv().await {
+        tokio::time::sleep(BATCH_WINDOW).await;
+
+        let mut resolved = vec![first];
+        while let Ok(result) = results.try_recv() {
+            resolved.push(result);
+        }
+
+        let batch: Vec<(String, image::Handle)> = {
+            let mut waiters = waiters.lock().unwrap_or_else(|e| e.into_inner());
+            resolved
+                .into_iter()
+                .flat_map(|(key, handle)| {
+                    let ids = waiters.remove(&key).unwrap_or_default();
+                    ids.into_iter().map(move |id| (id, handle.clone()))
+                })
+                .collect()
+        };
+
+        let _ = output.send(Message::IconsResolved(batch)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str, scale: u32) -> IconKey {
+        IconKey {
+            theme: "hicolor".to_string(),
+            name: name.to_string(),
+            size: 32,
+            scale,
+            theme_fingerprint: 0,
+        }
+    }
+
+    #[test]
+    fn same_icon_at_another_scale_is_looked_up_separately() {
+        let (jobs, mut queued) = mpsc::unbounded_channel();
+        let service = IconService {
+            jobs,
+            waiters: Waiters::default(),
+        };
+
+        service.request("a".to_string(), key("firefox", 1));
+        service.request("b".to_string(), key("firefox", 2));
+        service.request("c".to_string(), key("firefox", 1));
+
+        assert_eq!(queued.try_recv().unwrap(), key("firefox", 1));
+        assert_eq!(queued.try_recv().unwrap(), key("firefox", 2));
+        assert!(queued.try_recv().is_err());
+
+        let waiters = service.waiters.lock().unwrap();
+        assert_eq!(waiters[&key("firefox", 1)], ["a", "c"]);
+        assert_eq!(waiters[&key("firefox", 2)], ["b"]);
+    }
+}
@@ -0,0 +1,519 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::ui::icon::ICON_EXTENSIONS;
+
+const FALLBACK_THEME: &str = "hicolor";
+
+/// The last lookup built, with the theme preference it was built for.
+static SHARED: Mutex<Option<(Option<String>, Arc<IconLookup>)>> = Mutex::new(None);
+
+/// Resolves icon names against the user's icon theme, its `Inherits=` chain
+/// and `hicolor`, following the freedesktop Icon Theme specification.
+pub struct IconLookup {
+    theme_name: String,
+    /// Themes in lookup order: the user theme, its ancestors depth-first, then `hicolor`.
+    themes: Vec<IconTheme>,
+    pixmap_dirs: Vec<PathBuf>,
+    /// Changes whenever an icon or a theme is installed or removed.
+    fingerprint: u64,
+}
+
+impl IconLookup {
+    /// The lookup for the `icon_theme` preference. Building it reads every theme
+    /// directory, so it is shared until [`IconLookup::reload`] builds another.
+    pub fn shared(theme_name: Option<&str>) -> Arc<Self> {
+        let mut shared = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((shared_theme_name, icon_lookup)) = shared.as_ref()
+            && shared_theme_name.as_deref() == theme_name
+        {
+            return icon_lookup.clone();
+        }
+
+        let icon_lookup = Arc::new(Self::new(theme_name));
+        *shared = Some((theme_name.map(str::to_string), icon_lookup.clone()));
+        icon_lookup
+    }
+
+    /// Builds the lookup again, picking up installed or removed themes, and
+    /// shares it from now on.
+    pub fn reload(theme_name: Option<&str>) -> Arc<Self> {
+        let icon_lookup = Arc::new(Self::new(theme_name));
+        let mut shared = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+        *shared = Some((theme_name.map(str::to_string), icon_lookup.clone()));
+        icon_lookup
+    }
+
+    fn new(theme_name: Option<&str>) -> Self {
+        let xdg_dirs = xdg::BaseDirectories::new();
+        let mut data_dirs = xdg_dirs.get_data_dirs();
+        data_dirs.insert(0, xdg_dirs.get_data_home().unwrap_or_default());
+
+        let mut base_dirs: Vec<PathBuf> = std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".icons"))
+            .into_iter()
+            .collect();
+        base_dirs.extend(data_dirs.iter().map(|dir| dir.join("icons")));
+
+        let theme_name = theme_name
+            .map(str::to_string)
+            .or_else(detect_theme_name)
+            .unwrap_or_else(|| FALLBACK_THEME.to_string());
+        tracing::debug!(theme = theme_name, "Using icon theme.");
+
+        let pixmap_dirs = data_dirs.iter().map(|dir| dir.join("pixmaps")).collect();
+        Self::with_dirs(theme_name, base_dirs, pixmap_dirs)
+    }
+
+    /// Looks themes up in `base_dirs`, then icons without a theme in `pixmap_dirs`.
+    fn with_dirs(theme_name: String, base_dirs: Vec<PathBuf>, pixmap_dirs: Vec<PathBuf>) -> Self {
+        let mut themes = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![theme_name.clone()];
+
+        while let Some(name) = pending.pop() {
+            if !visited.insert(name.clone()) {
+                continue;
+            }
+
+            let Some(theme) = IconTheme::load(&name, &base_dirs) else {
+                continue;
+            };
+
+            // Pushed in reverse so the first parent is looked up first.
+            pending.extend(theme.inherits.iter().rev().cloned());
+            themes.push(theme);
+        }
+
+        if !visited.contains(FALLBACK_THEME)
+            && let Some(theme) = IconTheme::load(FALLBACK_THEME, &base_dirs)
+        {
+            themes.push(theme);
+        }
+
+        let fingerprint = fingerprint(&base_dirs, &themes, &pixmap_dirs);
+
+        Self {
+            theme_name,
+            themes,
+            pixmap_dirs,
+            fingerprint,
+        }
+    }
+
+    pub fn theme_name(&self) -> &str {
+        &self.theme_name
+    }
+
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    pub fn find_icon(&self, icon_name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        let path_iconname = Path::new(icon_name);
+        if path_iconname.is_absolute() {
+            return path_iconname.exists().then(|| path_iconname.to_path_buf());
+        }
+
+        self.themes
+            .iter()
+            .find_map(|theme| theme.lookup(icon_name, size, scale))
+            .or_else(|| self.find_pixmap(icon_name))
+    }
+
+    fn find_pixmap(&self, icon_name: &str) -> Option<PathBuf> {
+        self.pixmap_dirs.iter().find_map(|dir| {
+            ICON_EXTENSIONS
+                .iter()
+                .map(|ext| dir.join(format!("{icon_name}.{ext}")))
+                .find(|path| path.is_file())
+        })
+    }
+}
+
+/// Hashes the modification times of every directory icons are looked up in.
+/// Adding or removing a file changes the time of its directory, and installing
+/// a theme the time of its base directory.
+fn fingerprint(base_dirs: &[PathBuf], themes: &[IconTheme], pixmap_dirs: &[PathBuf]) -> u64 {
+    let theme_dirs = themes.iter().flat_map(|theme| {
+        theme.roots.iter().flat_map(|root| {
+            std::iter::once(root.clone())
+                .chain(theme.directories.iter().map(|dir| root.join(&dir.path)))
+        })
+    });
+
+    let mut hasher = DefaultHasher::new();
+    for dir in base_dirs
+        .iter()
+        .cloned()
+        .chain(theme_dirs)
+        .chain(pixmap_dirs.iter().cloned())
+    {
+        let modified = dir.metadata().and_then(|metadata| metadata.modified()).ok();
+        (dir, modified).hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+struct IconTheme {
+    /// Every `<base dir>/<theme name>` that exists, as a theme may span several.
+    roots: Vec<PathBuf>,
+    directories: Vec<ThemeDirectory>,
+    inherits: Vec<String>,
+}
+
+impl IconTheme {
+    fn load(name: &str, base_dirs: &[PathBuf]) -> Option<Self> {
+        let roots: Vec<PathBuf> = base_dirs
+            .iter()
+            .map(|dir| dir.join(name))
+            .filter(|root| root.is_dir())
+            .collect();
+
+        let content = roots
+            .iter()
+            .find_map(|root| std::fs::read_to_string(root.join("index.theme")).ok())?;
+        let groups = parse_ini(&content);
+        let header = groups.get("Icon Theme")?;
+
+        let directories = ["Directories", "ScaledDirectories"]
+            .iter()
+            .filter_map(|key| header.get(*key))
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|dir| !dir.is_empty())
+            .filter_map(|dir| ThemeDirectory::from_group(dir, groups.get(dir)?))
+            .collect();
+
+        let inherits = header
+            .get("Inherits")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|parent| !parent.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            roots,
+            directories,
+            inherits,
+        })
+    }
+
+    fn lookup(&self, icon_name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        let candidates = || {
+            self.directories.iter().flat_map(move |directory| {
+                self.roots.iter().flat_map(move |root| {
+                    ICON_EXTENSIONS.iter().map(move |ext| {
+                        let path = root
+                            .join(&directory.path)
+                            .join(format!("{icon_name}.{ext}"));
+                        (directory, path)
+                    })
+                })
+            })
+        };
+
+        let exact_match = candidates()
+            .filter(|(directory, _)| directory.matches_size(size, scale))
+            .find(|(_, path)| path.is_file())
+            .map(|(_, path)| path);
+
+        exact_match.or_else(|| {
+            candidates()
+                .filter(|(_, path)| path.is_file())
+                .min_by_key(|(directory, _)| directory.size_distance(size, scale))
+                .map(|(_, path)| path)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirectoryType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+struct ThemeDirectory {
+    path: String,
+    size: u32,
+    scale: u32,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+    kind: DirectoryType,
+}
+
+impl ThemeDirectory {
+    fn from_group(path: &str, group: &HashMap<String, String>) -> Option<Self> {
+        let number = |key: &str| group.get(key).and_then(|value| value.parse::<u32>().ok());
+        let size = number("Size")?;
+        let kind = match group.get("Type").map(String::as_str) {
+            Some("Fixed") => DirectoryType::Fixed,
+            Some("Scalable") => DirectoryType::Scalable,
+            _ => DirectoryType::Threshold,
+        };
+
+        Some(Self {
+            path: path.to_string(),
+            size,
+            scale: number("Scale").unwrap_or(1),
+            min_size: number("MinSize").unwrap_or(size),
+            max_size: number("MaxSize").unwrap_or(size),
+            threshold: number("Threshold").unwrap_or(2),
+            kind,
+        })
+    }
+
+    /// The range of sizes this directory can serve without scaling the icon.
+    fn size_range(&self) -> (u32, u32) {
+        match self.kind {
+            DirectoryType::Fixed => (self.size, self.size),
+            DirectoryType::Scalable => (self.min_size, self.max_size),
+            DirectoryType::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        }
+    }
+
+    fn matches_size(&self, size: u32, scale: u32) -> bool {
+        let (min, max) = self.size_range();
+        self.scale == scale && (min..=max).contains(&size)
+    }
+
+    fn size_distance(&self, size: u32, scale: u32) -> u32 {
+        let scaled_size = size * scale;
+        let (min, max) = self.size_range();
+
+        if scaled_size < min * self.scale {
+            min * self.scale - scaled_size
+        } else {
+            scaled_size.saturating_sub(max * self.scale)
+        }
+    }
+}
+
+/// Reads the icon theme configured for GTK or KDE, in that order.
+fn detect_theme_name() -> Option<String> {
+    let xdg_dirs = xdg::BaseDirectories::new();
+    let sources = [
+        ("gtk-4.0/settings.ini", "Settings", "gtk-icon-theme-name"),
+        ("gtk-3.0/settings.ini", "Settings", "gtk-icon-theme-name"),
+        ("kdeglobals", "Icons", "Theme"),
+    ];
+
+    sources.iter().find_map(|(file, group, key)| {
+        let path = xdg_dirs.find_config_file(file)?;
+        let content = std::fs::read_to_string(path).ok()?;
+        let value = parse_ini(&content).get(*group)?.get(*key)?.clone();
+        let value = value.trim_matches('"').to_string();
+
+        (!value.is_empty()).then_some(value)
+    })
+}
+
+fn parse_ini(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current_group = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current_group = Some(group.to_string());
+            continue;
+        }
+
+        if let (Some(group), Some((key, value))) = (&current_group, line.split_once('=')) {
+            groups
+                .entry(group.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory under the system temporary directory, removed on drop.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "{}-theme-{name}-{}",
+                env!("CARGO_PKG_NAME"),
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn write(&self, relative_path: &str, content: &str) -> PathBuf {
+            let path = self.0.join(relative_path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            path
+        }
+
+        fn lookup(&self, theme_name: &str) -> IconLookup {
+            IconLookup::with_dirs(
+                theme_name.to_string(),
+                vec![self.0.join("icons")],
+                vec![self.0.join("pixmaps")],
+            )
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn index_theme(inherits: &str, directories: &[(&str, &str)]) -> String {
+        let names: Vec<&str> = directories.iter().map(|(name, _)| *name).collect();
+        let mut content = format!(
+            "[Icon Theme]\nName=Test\nInherits={inherits}\nDirectories={}\n",
+            names.join(",")
+        );
+        for (name, keys) in directories {
+            content.push_str(&format!("\n[{name}]\n{keys}\n"));
+        }
+        content
+    }
+
+    #[test]
+    fn exact_size_is_preferred_then_the_closest_one() {
+        let dir = TestDir::new("sizes");
+        dir.write(
+            "icons/test/index.theme",
+            &index_theme(
+                "",
+                &[
+                    ("16x16/apps", "Size=16\nType=Fixed"),
+                    ("48x48/apps", "Size=48\nType=Fixed"),
+                ],
+            ),
+        );
+        let small = dir.write("icons/test/16x16/apps/editor.png", "");
+        let large = dir.write("icons/test/48x48/apps/editor.png", "");
+        let lookup = dir.lookup("test");
+
+        assert_eq!(lookup.find_icon("editor", 16, 1), Some(small.clone()));
+        assert_eq!(lookup.find_icon("editor", 48, 1), Some(large.clone()));
+        assert_eq!(lookup.find_icon("editor", 40, 1), Some(large));
+        assert_eq!(lookup.find_icon("editor", 20, 1), Some(small));
+    }
+
+    #[test]
+    fn scalable_and_threshold_directories_cover_a_size_range() {
+        let dir = TestDir::new("ranges");
+        dir.write(
+            "icons/test/index.theme",
+            &index_theme(
+                "",
+                &[
+                    ("16x16/apps", "Size=16\nType=Fixed"),
+                    ("32x32/apps", "Size=32\nType=Threshold\nThreshold=4"),
+                    (
+                        "scalable/apps",
+                        "Size=64\nType=Scalable\nMinSize=64\nMaxSize=256",
+                    ),
+                ],
+            ),
+        );
+        let fixed = dir.write("icons/test/16x16/apps/editor.png", "");
+        let threshold = dir.write("icons/test/32x32/apps/editor.png", "");
+        let scalable = dir.write("icons/test/scalable/apps/editor.svg", "");
+        let lookup = dir.lookup("test");
+
+        assert_eq!(lookup.find_icon("editor", 16, 1), Some(fixed));
+        assert_eq!(lookup.find_icon("editor", 35, 1), Some(threshold.clone()));
+        assert_eq!(lookup.find_icon("editor", 28, 1), Some(threshold));
+        assert_eq!(lookup.find_icon("editor", 200, 1), Some(scalable.clone()));
+        assert_eq!(lookup.find_icon("editor", 512, 1), Some(scalable));
+    }
+
+    #[test]
+    fn scaled_directory_is_chosen_for_its_scale() {
+        let dir = TestDir::new("scales");
+        dir.write(
+            "icons/test/index.theme",
+            "[Icon Theme]\nName=Test\nDirectories=32x32/apps\nScaledDirectories=32x32@2/apps\n\
+            \n[32x32/apps]\nSize=32\nType=Fixed\n\
+            \n[32x32@2/apps]\nSize=32\nScale=2\nType=Fixed\n",
+        );
+        let unscaled = dir.write("icons/test/32x32/apps/editor.png", "");
+        let scaled = dir.write("icons/test/32x32@2/apps/editor.png", "");
+        let lookup = dir.lookup("test");
+
+        assert_eq!(lookup.find_icon("editor", 32, 1), Some(unscaled));
+        assert_eq!(lookup.find_icon("editor", 32, 2), Some(scaled));
+    }
+
+    #[test]
+    fn inherited_themes_are_searched_depth_first_without_looping() {
+        let dir = TestDir::new("inherits");
+        let apps = [("apps", "Size=48\nType=Fixed")];
+        dir.write(
+            "icons/child/index.theme",
+            &index_theme("parent,other", &apps),
+        );
+        // Inheriting back from the child must not loop.
+        dir.write(
+            "icons/parent/index.theme",
+            &index_theme("grandparent,child", &apps),
+        );
+        dir.write("icons/grandparent/index.theme", &index_theme("", &apps));
+        dir.write("icons/other/index.theme", &index_theme("", &apps));
+
+        dir.write("icons/other/apps/editor.png", "");
+        let from_grandparent = dir.write("icons/grandparent/apps/editor.png", "");
+        let from_parent = dir.write("icons/parent/apps/browser.png", "");
+        dir.write("icons/other/apps/browser.png", "");
+        let from_other = dir.write("icons/other/apps/terminal.png", "");
+        let lookup = dir.lookup("child");
+
+        assert_eq!(lookup.themes.len(), 4);
+        assert_eq!(lookup.find_icon("browser", 48, 1), Some(from_parent));
+        assert_eq!(lookup.find_icon("editor", 48, 1), Some(from_grandparent));
+        assert_eq!(lookup.find_icon("terminal", 48, 1), Some(from_other));
+    }
+
+    #[test]
+    fn hicolor_then_pixmaps_are_the_last_resort() {
+        let dir = TestDir::new("fallbacks");
+        let apps = [("apps", "Size=48\nType=Fixed")];
+        dir.write("icons/test/index.theme", &index_theme("", &apps));
+        dir.write("icons/hicolor/index.theme", &index_theme("", &apps));
+        let from_hicolor = dir.write("icons/hicolor/apps/editor.png", "");
+        dir.write("pixmaps/editor.png", "");
+        let from_pixmaps = dir.write("pixmaps/legacy.png", "");
+        let lookup = dir.lookup("test");
+
+        assert_eq!(lookup.find_icon("editor", 48, 1), Some(from_hicolor));
+        assert_eq!(lookup.find_icon("legacy", 48, 1), Some(from_pixmaps));
+        assert_eq!(lookup.find_icon("missing", 48, 1), None);
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet};
+
+/// Marks a type whose globs from less important directories are discarded.
+const NO_GLOBS: &str = "__NOGLOBS__";
+/// Globs matched when a `globs2` line gives no weight.
+const DEFAULT_WEIGHT: u32 = 50;
+
+/// A type a file name matched, with what makes the match stronger than another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Strength {
+    weight: u32,
+    pattern_len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct GlobEntry {
+    mime: String,
+    strength: Strength,
+}
+
+/// File name patterns from `globs2` files, split by how they are matched:
+/// whole names and `*.ext` suffixes are looked up, the rest are wildcards.
+#[derive(Debug)]
+struct Wildcard {
+    pattern: glob::Pattern,
+    case_sensitive: bool,
+    entry: GlobEntry,
+}
+
+#[derive(Debug, Default)]
+pub struct Globs {
+    literals: HashMap<String, Vec<GlobEntry>>,
+    suffixes: HashMap<String, Vec<GlobEntry>>,
+    wildcards: Vec<Wildcard>,
+    /// Case-sensitive patterns are kept apart, everything else is lowercased.
+    case_sensitive_literals: HashMap<String, Vec<GlobEntry>>,
+    case_sensitive_suffixes: HashMap<String, Vec<GlobEntry>>,
+}
+
+impl Globs {
+    /// Adds the globs of a `globs2` file. `blocked` holds the types that more
+    /// important directories cleared with `__NOGLOBS__`, which are skipped and
+    /// extended with the ones this file clears.
+    pub fn extend_from(&mut self, content: &str, blocked: &mut HashSet<String>) {
+        let mut cleared_here = HashSet::new();
+
+        for line in content.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+
+            let mut fields = line.split(':');
+            let (Some(weight), Some(mime), Some(pattern)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+
+            if pattern == NO_GLOBS {
+                cleared_here.insert(mime.to_string());
+                continue;
+            }
+
+            if blocked.contains(mime) {
+                continue;
+            }
+
+            let case_sensitive = fields
+                .next()
+                .is_some_and(|flags| flags.split(',').any(|flag| flag == "cs"));
+            let entry = GlobEntry {
+                mime: mime.to_string(),
+                strength: Strength {
+                    weight: weight.parse().unwrap_or(DEFAULT_WEIGHT),
+                    pattern_len: pattern.len(),
+                },
+            };
+            self.insert(pattern, case_sensitive, entry);
+        }
+
+        blocked.extend(cleared_here);
+    }
+
+    fn insert(&mut self, pattern: &str, case_sensitive: bool, entry: GlobEntry) {
+        let has_wildcards = |s: &str| s.contains(['*', '?', '[']);
+        let pattern = if case_sensitive {
+            pattern.to_string()
+        } else {
+            pattern.to_lowercase()
+        };
+
+        let (literals, suffixes) = if case_sensitive {
+            (
+                &mut self.case_sensitive_literals,
+                &mut self.case_sensitive_suffixes,
+            )
+        } else {
+            (&mut self.literals, &mut self.suffixes)
+        };
+
+        if !has_wildcards(&pattern) {
+            literals.entry(pattern).or_default().push(entry);
+        } else if let Some(suffix) = pattern.strip_prefix('*')
+            && suffix.starts_with('.')
+            && !has_wildcards(suffix)
+        {
+            suffixes.entry(suffix.to_string()).or_default().push(entry);
+        } else if let Ok(pattern) = glob::Pattern::new(&pattern) {
+            self.wildcards.push(Wildcard {
+                pattern,
+                case_sensitive,
+                entry,
+            });
+        }
+    }
+
+    /// The types of the strongest patterns matching `file_name`. Several are
+    /// returned when patterns of the same weight and length disagree.
+    pub fn matches(&self, file_name: &str) -> Vec<String> {
+        let lowercase_name = file_name.to_lowercase();
+        let mut matched: Vec<&GlobEntry> = Vec::new();
+
+        matched.extend(self.literals.get(&lowercase_name).into_iter().flatten());
+        matched.extend(
+            self.case_sensitive_literals
+                .get(file_name)
+                .into_iter()
+                .flatten(),
+        );
+
+        // Every dot starts a suffix, so `.tar.gz` is found along with `.gz`.
+        for (index, _) in file_name.match_indices('.') {
+            matched.extend(
+                self.case_sensitive_suffixes
+                    .get(&file_name[index..])
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+        for (index, _) in lowercase_name.match_indices('.') {
+            matched.extend(
+                self.suffixes
+                    .get(&lowercase_name[index..])
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+
+        matched.extend(
+            self.wildcards
+                .iter()
+                .filter(|wildcard| {
+                    let name = if wildcard.case_sensitive {
+                        file_name
+                    } else {
+                        &lowercase_name
+                    };
+                    wildcard.pattern.matches(name)
+                })
+                .map(|wildcard| &wildcard.entry),
+        );
+
+        let Some(strongest) = matched.iter().map(|entry| entry.strength).max() else {
+            return Vec::new();
+        };
+
+        let mut mimes: Vec<String> = Vec::new();
+        for entry in matched
+            .into_iter()
+            .filter(|entry| entry.strength == strongest)
+        {
+            if !mimes.contains(&entry.mime) {
+                mimes.push(entry.mime.clone());
+            }
+        }
+
+        mimes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLOBS2: &str = "\
+# This file was automatically generated
+80:application/x-compressed-tar:*.tar.gz
+50:application/gzip:*.gz
+50:image/png:*.png
+50:text/x-csrc:*.c
+50:text/x-c++src:*.C:cs
+50:text/x-makefile:makefile
+10:text/x-readme:README*
+50:application/x-ole-storage:*.doc
+50:application/msword:*.doc
+";
+
+    fn globs(files: &[&str]) -> Globs {
+        let mut globs = Globs::default();
+        let mut blocked = HashSet::new();
+        for content in files {
+            globs.extend_from(content, &mut blocked);
+        }
+        globs
+    }
+
+    #[test]
+    fn matches_suffixes_and_literals_ignoring_case() {
+        let globs = globs(&[GLOBS2]);
+        assert_eq!(globs.matches("photo.PNG"), ["image/png"]);
+        assert_eq!(globs.matches("Makefile"), ["text/x-makefile"]);
+        assert_eq!(globs.matches("README.md"), ["text/x-readme"]);
+        assert!(globs.matches("notes").is_empty());
+    }
+
+    #[test]
+    fn case_sensitive_globs() {
+        let globs = globs(&[GLOBS2]);
+        assert_eq!(globs.matches("main.C"), ["text/x-c++src", "text/x-csrc"]);
+        assert_eq!(globs.matches("main.c"), ["text/x-csrc"]);
+    }
+
+    #[test]
+    fn strongest_pattern_wins() {
+        let globs = globs(&[GLOBS2]);
+        assert_eq!(
+            globs.matches("backup.tar.gz"),
+            ["application/x-compressed-tar"]
+        );
+        assert_eq!(globs.matches("backup.gz"), ["application/gzip"]);
+        // Equally strong patterns leave the choice to content sniffing.
+        assert_eq!(
+            globs.matches("letter.doc"),
+            ["application/x-ole-storage", "application/msword"]
+        );
+    }
+
+    #[test]
+    fn noglobs_clears_less_important_files() {
+        let user = "50:image/png:__NOGLOBS__\n50:image/x-custom:*.png\n";
+        let globs = globs(&[user, GLOBS2]);
+        assert_eq!(globs.matches("photo.png"), ["image/x-custom"]);
+    }
+}
@@ -0,0 +1,303 @@
+/// Header every `magic` file starts with.
+const MAGIC_HEADER: &[u8] = b"MIME-Magic\0\n";
+
+/// Byte patterns of a type, checked against the start of a file. A type
+/// matches when any top-level matchlet matches along with one of its children.
+#[derive(Debug)]
+struct MagicSection {
+    priority: u32,
+    mime: String,
+    matchlets: Vec<Matchlet>,
+}
+
+#[derive(Debug)]
+struct Matchlet {
+    offset: usize,
+    /// How many offsets after `offset` the value may also start at.
+    range: usize,
+    value: Vec<u8>,
+    mask: Option<Vec<u8>>,
+    children: Vec<Matchlet>,
+}
+
+impl Matchlet {
+    fn matches(&self, data: &[u8]) -> bool {
+        let found = (self.offset..self.offset + self.range).any(|start| {
+            let Some(window) = data.get(start..start + self.value.len()) else {
+                return false;
+            };
+
+            match &self.mask {
+                Some(mask) => window
+                    .iter()
+                    .zip(&self.value)
+                    .zip(mask)
+                    .all(|((byte, value), mask)| byte & mask == value & mask),
+                None => window == self.value,
+            }
+        });
+
+        found && (self.children.is_empty() || self.children.iter().any(|c| c.matches(data)))
+    }
+
+    /// The number of leading bytes needed to evaluate the matchlet and its children.
+    fn extent(&self) -> usize {
+        let own = self.offset + self.range - 1 + self.value.len();
+        self.children
+            .iter()
+            .map(Matchlet::extent)
+            .fold(own, usize::max)
+    }
+}
+
+/// Content sniffing rules from `magic` files, highest priority first.
+#[derive(Debug, Default)]
+pub struct Magic {
+    sections: Vec<MagicSection>,
+}
+
+impl Magic {
+    /// Adds the sections of a `magic` file, ignoring it when the header is missing.
+    pub fn extend_from(&mut self, content: &[u8]) {
+        let Some(mut rest) = content.strip_prefix(MAGIC_HEADER) else {
+            tracing::debug!("Skipping magic file without a valid header.");
+            return;
+        };
+
+        while !rest.is_empty() {
+            match parse_section(rest) {
+                Some((section, remaining)) => {
+                    self.sections.push(section);
+                    rest = remaining;
+                }
+                None => break,
+            }
+        }
+
+        // Stable, so sections of more important directories stay ahead on ties.
+        self.sections
+            .sort_by_key(|section| std::cmp::Reverse(section.priority));
+    }
+
+    /// How many leading bytes of a file all rules together look at.
+    pub fn extent(&self) -> usize {
+        self.sections
+            .iter()
+            .flat_map(|section| &section.matchlets)
+            .map(Matchlet::extent)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The type of the highest priority section matching `data`.
+    pub fn sniff(&self, data: &[u8]) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|section| section.matchlets.iter().any(|m| m.matches(data)))
+            .map(|section| section.mime.as_str())
+    }
+}
+
+/// Parses a `[priority:type]` header and its matchlets, returning the rest of the file.
+fn parse_section(input: &[u8]) -> Option<(MagicSection, &[u8])> {
+    let input = input.strip_prefix(b"[")?;
+    let header_end = input.iter().position(|&b| b == b'\n')?;
+    let header = std::str::from_utf8(&input[..header_end]).ok()?;
+    let (priority, mime) = header.strip_suffix(']')?.split_once(':')?;
+    let mut rest = &input[header_end + 1..];
+
+    // Matchlets come with their indent, children right after their parent.
+    let mut flat = Vec::new();
+    while !rest.is_empty() && rest[0] != b'[' {
+        match parse_matchlet(rest) {
+            Some((indent, matchlet, remaining)) => {
+                flat.push((indent, matchlet));
+                rest = remaining;
+            }
+            // Lines using unknown syntax are skipped as a whole.
+            None => rest = skip_line(rest),
+        }
+    }
+
+    let section = MagicSection {
+        priority: priority.parse().ok()?,
+        mime: mime.to_string(),
+        matchlets: nest(&mut flat.into_iter().peekable(), 0),
+    };
+
+    Some((section, rest))
+}
+
+/// Builds the matchlet tree from matchlets listed in order with their indent.
+fn nest(
+    flat: &mut std::iter::Peekable<impl Iterator<Item = (usize, Matchlet)>>,
+    indent: usize,
+) -> Vec<Matchlet> {
+    let mut matchlets = Vec::new();
+
+    while let Some((next_indent, _)) = flat.peek() {
+        if *next_indent < indent {
+            break;
+        }
+
+        let Some((next_indent, mut matchlet)) = flat.next() else {
+            break;
+        };
+        if next_indent > indent {
+            // A child without a parent at the level above, which cannot match.
+            continue;
+        }
+
+        matchlet.children = nest(flat, indent + 1);
+        matchlets.push(matchlet);
+    }
+
+    matchlets
+}
+
+/// Parses `[indent]>offset=<u16 length><value>[&mask][~word size][+range]\n`.
+fn parse_matchlet(input: &[u8]) -> Option<(usize, Matchlet, &[u8])> {
+    let (indent, rest) = take_number(input);
+    let rest = rest.strip_prefix(b">")?;
+    let (offset, rest) = take_number(rest);
+    let rest = rest.strip_prefix(b"=")?;
+
+    let (length, rest) = rest.split_first_chunk::<2>()?;
+    let length = u16::from_be_bytes(*length) as usize;
+    let (mut value, mut rest) = (rest.get(..length)?.to_vec(), rest.get(length..)?);
+
+    let mut mask = None;
+    if let Some(after) = rest.strip_prefix(b"&") {
+        mask = Some(after.get(..length)?.to_vec());
+        rest = after.get(length..)?;
+    }
+
+    let mut word_size = 1;
+    if let Some(after) = rest.strip_prefix(b"~") {
+        let (size, after) = take_number(after);
+        word_size = size?;
+        rest = after;
+    }
+
+    let mut range = 1;
+    if let Some(after) = rest.strip_prefix(b"+") {
+        let (length, after) = take_number(after);
+        range = length?;
+        rest = after;
+    }
+
+    let rest = rest.strip_prefix(b"\n")?;
+
+    // Values of multi-byte words are stored big-endian.
+    if cfg!(target_endian = "little") && (word_size == 2 || word_size == 4) {
+        value.chunks_exact_mut(word_size).for_each(<[u8]>::reverse);
+        if let Some(mask) = &mut mask {
+            mask.chunks_exact_mut(word_size).for_each(<[u8]>::reverse);
+        }
+    }
+
+    let matchlet = Matchlet {
+        offset: offset?,
+        range: range.max(1),
+        value,
+        mask,
+        children: Vec::new(),
+    };
+
+    Some((indent.unwrap_or(0), matchlet, rest))
+}
+
+fn take_number(input: &[u8]) -> (Option<usize>, &[u8]) {
+    let digits = input.iter().take_while(|b| b.is_ascii_digit()).count();
+    let number = std::str::from_utf8(&input[..digits])
+        .ok()
+        .and_then(|digits| digits.parse().ok());
+
+    (number, &input[digits..])
+}
+
+fn skip_line(input: &[u8]) -> &[u8] {
+    match input.iter().position(|&b| b == b'\n') {
+        Some(end) => &input[end + 1..],
+        None => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `[priority:mime]` section header.
+    fn section(priority: u32, mime: &str) -> Vec<u8> {
+        format!("[{priority}:{mime}]\n").into_bytes()
+    }
+
+    /// A matchlet line, `tail` holding the optional mask, word size and range.
+    fn matchlet(indent: &str, offset: usize, value: &[u8], tail: &[u8]) -> Vec<u8> {
+        let mut line = format!("{indent}>{offset}=").into_bytes();
+        line.extend((value.len() as u16).to_be_bytes());
+        line.extend(value);
+        line.extend(tail);
+        line.push(b'\n');
+        line
+    }
+
+    fn magic(parts: &[Vec<u8>]) -> Magic {
+        let mut content = MAGIC_HEADER.to_vec();
+        content.extend(parts.concat());
+        let mut magic = Magic::default();
+        magic.extend_from(&content);
+        magic
+    }
+
+    #[test]
+    fn sniffs_by_priority() {
+        let magic = magic(&[
+            section(40, "application/zip"),
+            matchlet("", 0, b"PK\x03\x04", b""),
+            section(70, "application/vnd.oasis.opendocument.text"),
+            matchlet("", 0, b"PK\x03\x04", b""),
+            matchlet("1", 30, b"mimetype", b""),
+            section(50, "image/png"),
+            matchlet("", 0, b"\x89PNG", b""),
+        ]);
+
+        assert_eq!(magic.sniff(b"\x89PNG\r\n"), Some("image/png"));
+        assert_eq!(
+            magic.sniff(b"PK\x03\x04 plain archive"),
+            Some("application/zip")
+        );
+
+        let mut document = b"PK\x03\x04".to_vec();
+        document.resize(30, 0);
+        document.extend(b"mimetype");
+        assert_eq!(
+            magic.sniff(&document),
+            Some("application/vnd.oasis.opendocument.text")
+        );
+        assert_eq!(magic.sniff(b"plain text"), None);
+        assert_eq!(magic.extent(), 38);
+    }
+
+    #[test]
+    fn masks_and_ranges() {
+        let magic = magic(&[
+            section(50, "text/x-masked"),
+            matchlet("", 0, b"AB", b"&\xdf\xdf"),
+            section(50, "text/x-ranged"),
+            matchlet("", 0, b"needle", b"+10"),
+        ]);
+
+        // The mask clears the lowercase bit.
+        assert_eq!(magic.sniff(b"ab"), Some("text/x-masked"));
+        assert_eq!(magic.sniff(b"...needle"), Some("text/x-ranged"));
+        assert_eq!(magic.sniff(b"............needle"), None);
+    }
+
+    #[test]
+    fn ignores_files_without_header() {
+        let mut magic = Magic::default();
+        magic.extend_from(&[section(50, "image/png"), matchlet("", 0, b"\x89PNG", b"")].concat());
+        assert_eq!(magic.sniff(b"\x89PNG"), None);
+    }
+}
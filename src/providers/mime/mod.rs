@@ -0,0 +1,293 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Read,
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+mod globs;
+mod magic;
+
+use globs::Globs;
+use magic::Magic;
+
+/// The shared-mime-info database of the system, loaded on first use.
+pub static MIME_DATABASE: LazyLock<MimeDatabase> = LazyLock::new(MimeDatabase::load);
+
+pub const DIRECTORY: &str = "inode/directory";
+const SYMLINK: &str = "inode/symlink";
+const CHAR_DEVICE: &str = "inode/chardevice";
+const BLOCK_DEVICE: &str = "inode/blockdevice";
+const FIFO: &str = "inode/fifo";
+const SOCKET: &str = "inode/socket";
+const EMPTY: &str = "application/x-zerosize";
+const PLAIN_TEXT: &str = "text/plain";
+const EXECUTABLE: &str = "application/x-executable";
+const BINARY: &str = "application/octet-stream";
+
+/// Content sniffing reads at most this many bytes, whatever the rules ask for.
+const MAX_SNIFF_LENGTH: usize = 64 * 1024;
+/// Bytes looked at to tell text from binary data when nothing else matched.
+const TEXT_SNIFF_LENGTH: usize = 128;
+
+/// MIME types detection following the freedesktop shared-mime-info
+/// specification, from the `mime` directory of every XDG data directory.
+#[derive(Debug, Default)]
+pub struct MimeDatabase {
+    globs: Globs,
+    magic: Magic,
+    /// Leading bytes of a file that `magic` needs.
+    sniff_length: usize,
+    aliases: HashMap<String, String>,
+    parents: HashMap<String, Vec<String>>,
+    icons: HashMap<String, String>,
+    generic_icons: HashMap<String, String>,
+}
+
+impl MimeDatabase {
+    pub fn load() -> Self {
+        let mut database = Self::default();
+        let mut blocked_globs = HashSet::new();
+
+        // The most important directory is read first, and wins every conflict.
+        for mime_dir in mime_dirs() {
+            if let Ok(content) = fs::read_to_string(mime_dir.join("globs2")) {
+                database.globs.extend_from(&content, &mut blocked_globs);
+            }
+
+            if let Ok(content) = fs::read(mime_dir.join("magic")) {
+                database.magic.extend_from(&content);
+            }
+
+            for (alias, mime) in read_pairs(&mime_dir.join("aliases"), ' ') {
+                database.aliases.entry(alias).or_insert(mime);
+            }
+
+            for (mime, parent) in read_pairs(&mime_dir.join("subclasses"), ' ') {
+                let parents = database.parents.entry(mime).or_default();
+                if !parents.contains(&parent) {
+                    parents.push(parent);
+                }
+            }
+
+            for (mime, icon) in read_pairs(&mime_dir.join("icons"), ':') {
+                database.icons.entry(mime).or_insert(icon);
+            }
+
+            for (mime, icon) in read_pairs(&mime_dir.join("generic-icons"), ':') {
+                database.generic_icons.entry(mime).or_insert(icon);
+            }
+        }
+
+        database.sniff_length = database.magic.extent().min(MAX_SNIFF_LENGTH);
+        database
+    }
+
+    /// Detects the type of the file at `path`, following symlinks. Broken links
+    /// and special files get their `inode/*` type.
+    pub fn detect(&self, path: &Path) -> String {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) if path.is_symlink() => return SYMLINK.to_string(),
+            Err(e) => {
+                tracing::debug!(error = %e, ?path, "Cannot read file metadata for its MIME type.");
+                return BINARY.to_string();
+            }
+        };
+
+        let file_type = metadata.file_type();
+        let special_type = if file_type.is_dir() {
+            Some(DIRECTORY)
+        } else if file_type.is_char_device() {
+            Some(CHAR_DEVICE)
+        } else if file_type.is_block_device() {
+            Some(BLOCK_DEVICE)
+        } else if file_type.is_fifo() {
+            Some(FIFO)
+        } else if file_type.is_socket() {
+            Some(SOCKET)
+        } else {
+            None
+        };
+
+        if let Some(special_type) = special_type {
+            return special_type.to_string();
+        }
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let globbed: Vec<String> = self
+            .globs
+            .matches(&file_name)
+            .iter()
+            .map(|mime| self.unalias(mime).to_string())
+            .collect();
+
+        if let [mime] = globbed.as_slice() {
+            return mime.clone();
+        }
+
+        if metadata.len() == 0 {
+            return globbed
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| EMPTY.to_string());
+        }
+
+        let data = read_head(path, self.sniff_length.max(TEXT_SNIFF_LENGTH));
+        let sniffed = self.magic.sniff(&data).map(|mime| self.unalias(mime));
+
+        // Globs are more precise than magic, which often finds a parent type,
+        // such as zip for a document.
+        if let Some(sniffed) = sniffed
+            && let Some(mime) = globbed.iter().find(|mime| self.is_subclass(mime, sniffed))
+        {
+            return mime.clone();
+        }
+
+        if let Some(mime) = globbed.into_iter().next() {
+            return mime;
+        }
+
+        if let Some(sniffed) = sniffed {
+            return sniffed.to_string();
+        }
+
+        let is_text = !data.is_empty() && !data[..data.len().min(TEXT_SNIFF_LENGTH)].contains(&0);
+        if is_text {
+            PLAIN_TEXT.to_string()
+        } else if is_executable(&metadata) {
+            EXECUTABLE.to_string()
+        } else {
+            BINARY.to_string()
+        }
+    }
+
+    /// The canonical name of `mime`, which may be an alias.
+    pub fn unalias<'a>(&'a self, mime: &'a str) -> &'a str {
+        self.aliases.get(mime).map_or(mime, String::as_str)
+    }
+
+    /// Whether `mime` is `parent` or inherits from it, including the implicit
+    /// `text/plain` and `application/octet-stream` parents.
+    pub fn is_subclass(&self, mime: &str, parent: &str) -> bool {
+        let parent = self.unalias(parent);
+        let mut visited = HashSet::new();
+        let mut pending = vec![self.unalias(mime)];
+
+        while let Some(mime) = pending.pop() {
+            if mime == parent {
+                return true;
+            }
+
+            if !visited.insert(mime) {
+                continue;
+            }
+
+            let is_implicit_parent = (parent == PLAIN_TEXT && mime.starts_with("text/"))
+                || (parent == BINARY && !mime.starts_with("inode/"));
+            if is_implicit_parent {
+                return true;
+            }
+
+            if let Some(parents) = self.parents.get(mime) {
+                pending.extend(parents.iter().map(|parent| self.unalias(parent)));
+            }
+        }
+
+        false
+    }
+
+    /// `mime` followed by every type it inherits from, closest first, ending with
+    /// the implicit `text/plain` parent of text types.
+    pub fn lineage(&self, mime: &str) -> Vec<String> {
+        let mut lineage = vec![self.unalias(mime).to_string()];
+        let mut index = 0;
+
+        while let Some(mime) = lineage.get(index) {
+            let parents = self.parents.get(mime.as_str()).into_iter().flatten();
+            let parents: Vec<String> = parents
+                .map(|parent| self.unalias(parent).to_string())
+                .filter(|parent| !lineage.contains(parent))
+                .collect();
+            lineage.extend(parents);
+            index += 1;
+        }
+
+        if lineage[0].starts_with("text/") && !lineage.iter().any(|mime| mime == PLAIN_TEXT) {
+            lineage.push(PLAIN_TEXT.to_string());
+        }
+
+        lineage
+    }
+
+    /// Icon names for `mime` from most to least specific: the icon the database
+    /// names, the type itself, then its generic icon.
+    pub fn icon_names(&self, mime: &str) -> Vec<String> {
+        let mime = self.unalias(mime);
+        let media = mime.split('/').next().unwrap_or(mime);
+        let generic_icon = self
+            .generic_icons
+            .get(mime)
+            .cloned()
+            .unwrap_or_else(|| format!("{media}-x-generic"));
+
+        let mut names: Vec<String> = self.icons.get(mime).cloned().into_iter().collect();
+        names.push(mime.replace('/', "-"));
+        names.push(generic_icon);
+        names.dedup();
+        names
+    }
+}
+
+/// Detects the type of the file at `path` on the blocking thread pool, since it
+/// may read the file, and the database on first use.
+pub async fn detect(path: PathBuf) -> String {
+    tokio::task::spawn_blocking(move || MIME_DATABASE.detect(&path))
+        .await
+        .unwrap_or_else(|_| BINARY.to_string())
+}
+
+/// `$XDG_DATA_HOME/mime` followed by the `mime` directory of each `$XDG_DATA_DIRS`.
+fn mime_dirs() -> Vec<PathBuf> {
+    let xdg_dirs = xdg::BaseDirectories::new();
+    xdg_dirs
+        .get_data_home()
+        .into_iter()
+        .chain(xdg_dirs.get_data_dirs())
+        .map(|dir| dir.join("mime"))
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// Reads the `key<separator>value` lines of a database file.
+fn read_pairs(path: &Path, separator: char) -> Vec<(String, String)> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(separator))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+fn read_head(path: &Path, length: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(length);
+    if let Ok(file) = fs::File::open(path) {
+        let _ = file.take(length as u64).read_to_end(&mut data);
+    }
+
+    data
+}
+
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
@@ -2,8 +2,10 @@ use crate::preferences::Preferences;
 use crate::providers::app::AppProvider;
 use crate::providers::clipboard::ClipboardProvider;
 use crate::providers::file::FileProvider;
+use crate::providers::run::RunProvider;
 use crate::ui::entry::Entry;
 use std::hash::{Hash, Hasher};
+use std::os::unix::fs::PermissionsExt;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::{io, os::unix::process::CommandExt, path::PathBuf, process};
@@ -11,12 +13,18 @@ use std::{io, os::unix::process::CommandExt, path::PathBuf, process};
 use iced::futures::channel::mpsc::Sender as FuturesSender;
 use iced::futures::{SinkExt, Stream};
 use iced::{Subscription, Task};
+use tokio::sync::watch;
 
 use crate::launcher::Message;
 
 pub mod app;
 pub mod clipboard;
 pub mod file;
+pub mod icon;
+pub mod mime;
+pub mod run;
+pub mod terminal;
+pub mod usage;
 
 pub trait Provider {
     fn scan(request: ScanRequest) -> impl Stream<Item = Message>;
@@ -28,6 +36,7 @@ pub enum ProviderKind {
     App,
     File,
     Clipboard,
+    Run,
 }
 
 impl FromStr for ProviderKind {
@@ -38,17 +47,59 @@ impl FromStr for ProviderKind {
             "app" => Ok(Self::App),
             "file" => Ok(Self::File),
             "clipboard" => Ok(Self::Clipboard),
+            "run" => Ok(Self::Run),
             _ => Err("Invalid provider kind".to_string()),
         }
     }
 }
 
+impl std::fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::App => write!(f, "app"),
+            Self::File => write!(f, "file"),
+            Self::Clipboard => write!(f, "clipboard"),
+            Self::Run => write!(f, "run"),
+        }
+    }
+}
+
 impl ProviderKind {
-    pub fn launch(&self, entry: &Entry) -> Task<Message> {
+    /// Launches `entry`, selected while `prompt` was typed.
+    pub fn launch(&self, entry: &Entry, prompt: &str) -> Task<Message> {
         match self {
             ProviderKind::App => AppProvider::launch(entry),
             ProviderKind::File => FileProvider::launch(entry),
             ProviderKind::Clipboard => ClipboardProvider::launch(entry),
+            ProviderKind::Run => RunProvider::launch_with_arguments(entry, prompt),
+        }
+    }
+
+    /// Launches `entry` inside `terminal`. Returns `None` for providers whose
+    /// entries are not commands.
+    pub fn launch_in_terminal(
+        &self,
+        entry: &Entry,
+        prompt: &str,
+        terminal: &[String],
+    ) -> Option<Task<Message>> {
+        match self {
+            ProviderKind::Run => Some(RunProvider::launch_in_terminal(entry, prompt, terminal)),
+            _ => None,
+        }
+    }
+
+    /// Whether launches are written to the usage history. Clipboard entries
+    /// are identified by the copied text, which may be a password.
+    pub fn records_usage(&self) -> bool {
+        !matches!(self, ProviderKind::Clipboard)
+    }
+
+    /// The part of the prompt entries are matched against.
+    pub fn search_query<'a>(&self, prompt: &'a str) -> &'a str {
+        match self {
+            ProviderKind::Run => run::program_query(prompt),
+            _ => prompt,
         }
     }
 }
@@ -58,12 +109,26 @@ pub struct ScanRequest {
     pub path: PathBuf,
     pub provider: ProviderKind,
     pub preferences: Preferences,
+    /// Integer scale factor icons are resolved and rasterized for. Providers
+    /// follow its changes instead of scanning again.
+    pub icon_scale: watch::Receiver<u32>,
+    /// What a recursive file search looks for below `path`. The directory is
+    /// listed instead when unset.
+    pub query: Option<String>,
+    /// The file whose applications are listed instead of the directory, for
+    /// "Open with".
+    pub open_with: Option<PathBuf>,
 }
 
 impl Hash for ScanRequest {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.path.hash(state);
         self.provider.hash(state);
+        self.query.hash(state);
+        self.open_with.hash(state);
+        // Includes the listing options toggled at runtime, which list the
+        // directory again.
+        self.preferences.hash_scan_settings(state);
     }
 }
 
@@ -77,6 +142,7 @@ impl ScanRequest {
             ProviderKind::Clipboard => {
                 Subscription::run_with(self, |ctx| ClipboardProvider::scan(ctx.clone()))
             }
+            ProviderKind::Run => Subscription::run_with(self, |ctx| RunProvider::scan(ctx.clone())),
         }
     }
 }
@@ -86,6 +152,10 @@ pub enum ScannerState {
     Started,
     Found(Vec<Entry>),
     Finished,
+    /// Entries added or replaced after the scan finished, matched by ID.
+    Updated(Vec<Entry>),
+    /// IDs of entries that disappeared after the scan finished.
+    Removed(Vec<String>),
     Errored(Arc<anyhow::Error>),
 }
 
@@ -137,6 +207,24 @@ impl Scanner {
             .await;
     }
 
+    async fn update(&mut self, entries: Vec<Entry>) {
+        if !entries.is_empty() {
+            let _ = self
+                .sender
+                .send(Message::ScanEvent(ScannerState::Updated(entries)))
+                .await;
+        }
+    }
+
+    async fn remove(&mut self, ids: Vec<String>) {
+        if !ids.is_empty() {
+            let _ = self
+                .sender
+                .send(Message::ScanEvent(ScannerState::Removed(ids)))
+                .await;
+        }
+    }
+
     pub async fn error(&mut self, e: anyhow::Error) {
         let _ = self
             .sender
@@ -163,3 +251,125 @@ fn spawn_with_new_session(command: &mut process::Command) -> io::Result<process:
 
     command.spawn()
 }
+
+/// Looks up an executable the same way a shell would, through `$PATH`.
+pub fn find_in_path(binary: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Checks that `program` can be executed, either as a path or as a name on `$PATH`.
+pub fn is_program_available(program: &str) -> bool {
+    if program.contains('/') {
+        return is_executable(std::path::Path::new(program));
+    }
+
+    find_in_path(program).is_some()
+}
+
+pub fn is_executable(path: &std::path::Path) -> bool {
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, hash::DefaultHasher};
+
+    use super::*;
+    use crate::preferences::{AppPreferences, CustomEntry};
+
+    fn request(preferences: Preferences) -> ScanRequest {
+        ScanRequest {
+            path: PathBuf::from("/home/user"),
+            provider: ProviderKind::App,
+            preferences,
+            icon_scale: watch::channel(1).1,
+            query: None,
+            open_with: None,
+        }
+    }
+
+    fn hash(request: &ScanRequest) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        request.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Asserts that a scan started with the default preferences starts again
+    /// once `loaded` replaces them.
+    fn assert_restarts(loaded: Preferences) {
+        assert_ne!(
+            hash(&request(loaded)),
+            hash(&request(Preferences::default()))
+        );
+    }
+
+    #[test]
+    fn loaded_preferences_restart_the_scan() {
+        assert_restarts(Preferences {
+            custom_entries: vec![CustomEntry {
+                name: "VPN up".to_string(),
+                comment: None,
+                icon: None,
+                command: "nmcli con up work".to_string(),
+            }],
+            ..Preferences::default()
+        });
+    }
+
+    #[test]
+    fn loaded_locale_restarts_the_scan() {
+        assert_restarts(Preferences {
+            locale: Some("de_DE".to_string()),
+            ..Preferences::default()
+        });
+    }
+
+    #[test]
+    fn loaded_terminal_restarts_the_scan() {
+        assert_restarts(Preferences {
+            terminal: Some("foot -e".to_string()),
+            ..Preferences::default()
+        });
+    }
+
+    #[test]
+    fn loaded_show_broken_apps_restarts_the_scan() {
+        assert_restarts(Preferences {
+            show_broken_apps: true,
+            ..Preferences::default()
+        });
+    }
+
+    #[test]
+    fn loaded_icon_theme_restarts_the_scan() {
+        assert_restarts(Preferences {
+            icon_theme: Some("Papirus".to_string()),
+            ..Preferences::default()
+        });
+    }
+
+    #[test]
+    fn loaded_app_preferences_restart_the_scan() {
+        let app_preferences = AppPreferences {
+            wrapper: vec!["prime-run".to_string()],
+            ..AppPreferences::default()
+        };
+        assert_restarts(Preferences {
+            apps: HashMap::from([("steam.desktop".to_string(), app_preferences)]),
+            ..Preferences::default()
+        });
+    }
+
+    #[test]
+    fn favorites_do_not_restart_the_scan() {
+        let default = hash(&request(Preferences::default()));
+
+        let mut preferences = Preferences::default();
+        preferences.toggle_favorite("firefox.desktop");
+        assert_eq!(hash(&request(preferences)), default);
+    }
+}
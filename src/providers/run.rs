@@ -0,0 +1,179 @@
+use std::{collections::HashSet, path::PathBuf, process};
+
+use iced::{Task, futures::Stream, window};
+
+use crate::{
+    launcher::Message,
+    providers::{ScanRequest, Scanner},
+    ui::{
+        entry::{Entry, EntryIcon},
+        icon::APPLICATION_DEFAULT,
+    },
+};
+
+use super::{Provider, is_executable, spawn_with_new_session, terminal::wrap_in_terminal};
+
+/// Lists the executables found on `$PATH`, for programs without a desktop file.
+#[derive(Debug, Clone, Copy)]
+pub struct RunProvider;
+
+impl Provider for RunProvider {
+    fn scan(request: ScanRequest) -> impl Stream<Item = Message> {
+        iced::stream::channel(100, async move |output| {
+            let mut scanner = Scanner::new(output, request.preferences.scan_batch_size);
+            scanner.start().await;
+
+            let executables = tokio::task::spawn_blocking(find_executables)
+                .await
+                .unwrap_or_default();
+
+            for (name, path) in executables {
+                let entry = Entry::new(
+                    name.clone(),
+                    name,
+                    Some(path.to_string_lossy()),
+                    EntryIcon::Handle(APPLICATION_DEFAULT.clone()),
+                )
+                .command(vec![path.to_string_lossy().into_owned()]);
+                scanner.load(entry).await;
+            }
+
+            scanner.finish().await;
+        })
+    }
+
+    fn launch(entry: &Entry) -> Task<Message> {
+        let [binary, args @ ..] = entry.command.as_slice() else {
+            tracing::warn!("Launch failed: provided entry resulted in an empty command.");
+            return Task::none();
+        };
+
+        let mut command = process::Command::new(binary);
+        command.args(args);
+        tracing::info!(binary = ?binary, args = ?args, "Attempting to launch detached process.");
+
+        if let Err(e) = spawn_with_new_session(&mut command) {
+            tracing::error!(error = %e, binary = ?binary, "Failed to spawn process.");
+            return Task::none();
+        }
+
+        tracing::info!(binary = ?binary, "Process launched successfully.");
+        window::latest().and_then(window::close)
+    }
+}
+
+impl RunProvider {
+    /// Launches the entry with the arguments typed after its name in the prompt.
+    pub fn launch_with_arguments(entry: &Entry, prompt: &str) -> Task<Message> {
+        Self::launch(&with_arguments(entry, prompt))
+    }
+
+    /// Like [`RunProvider::launch_with_arguments`], inside `terminal`.
+    pub fn launch_in_terminal(entry: &Entry, prompt: &str, terminal: &[String]) -> Task<Message> {
+        let entry = with_arguments(entry, prompt);
+        let command = wrap_in_terminal(terminal, entry.command.clone());
+        Self::launch(&entry.command(command))
+    }
+}
+
+/// The program name at the start of the prompt, which is all entries are matched against.
+pub fn program_query(prompt: &str) -> &str {
+    split_prompt(prompt).0
+}
+
+fn split_prompt(prompt: &str) -> (&str, &str) {
+    let prompt = prompt.trim_start();
+    prompt
+        .split_once(char::is_whitespace)
+        .unwrap_or((prompt, ""))
+}
+
+/// Appends the arguments typed after the program name, split like a shell would.
+fn with_arguments(entry: &Entry, prompt: &str) -> Entry {
+    let (_, arguments) = split_prompt(prompt);
+    let arguments = shlex::split(arguments).unwrap_or_else(|| {
+        tracing::warn!(
+            arguments,
+            "Unbalanced quotes in arguments, splitting on whitespace."
+        );
+        arguments.split_whitespace().map(str::to_string).collect()
+    });
+
+    let command = entry.command.iter().cloned().chain(arguments).collect();
+    entry.clone().command(command)
+}
+
+/// Walks `$PATH` in order, keeping the first executable found for every name,
+/// like a shell resolving a command would. Results are sorted by name.
+fn find_executables() -> Vec<(String, PathBuf)> {
+    let Some(path_var) = std::env::var_os("PATH") else {
+        tracing::warn!("$PATH is not set, no executables to list.");
+        return Vec::new();
+    };
+
+    let mut seen_names = HashSet::new();
+    let mut executables = Vec::new();
+
+    for dir in std::env::split_paths(&path_var) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            tracing::debug!(path = ?dir, "Skipping unreadable $PATH directory.");
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+
+            let path = entry.path();
+            if seen_names.contains(&name) || !is_executable(&path) {
+                continue;
+            }
+
+            seen_names.insert(name.clone());
+            executables.push((name, path));
+        }
+    }
+
+    executables.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    executables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> Entry {
+        Entry::new(
+            "grep",
+            "grep",
+            None::<String>,
+            EntryIcon::Handle(APPLICATION_DEFAULT.clone()),
+        )
+        .command(vec!["/usr/bin/grep".to_string()])
+    }
+
+    #[test]
+    fn split_prompt_skips_leading_whitespace() {
+        assert_eq!(split_prompt("  grep -r  todo"), ("grep", "-r  todo"));
+        assert_eq!(program_query("\tgrep -r"), "grep");
+    }
+
+    #[test]
+    fn bare_program_name_has_no_arguments() {
+        assert_eq!(split_prompt("grep"), ("grep", ""));
+        assert_eq!(with_arguments(&entry(), "grep").command, ["/usr/bin/grep"]);
+    }
+
+    #[test]
+    fn quoted_arguments_stay_together() {
+        let entry = with_arguments(&entry(), r#"grep -e "two words" 'it''s'"#);
+        assert_eq!(entry.command, ["/usr/bin/grep", "-e", "two words", "its"]);
+    }
+
+    #[test]
+    fn unbalanced_quotes_fall_back_to_whitespace() {
+        let entry = with_arguments(&entry(), r#"grep "two words"#);
+        assert_eq!(entry.command, ["/usr/bin/grep", "\"two", "words"]);
+    }
+}
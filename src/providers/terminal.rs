@@ -0,0 +1,64 @@
+use super::find_in_path;
+
+/// Terminal emulators probed when no `terminal` preference is set, paired
+/// with the arguments that make them run the command that follows.
+const KNOWN_TERMINALS: [(&str, &[&str]); 9] = [
+    ("foot", &[]),
+    ("kitty", &["--"]),
+    ("alacritty", &["-e"]),
+    ("wezterm", &["start", "--"]),
+    ("ghostty", &["-e"]),
+    ("konsole", &["-e"]),
+    ("gnome-terminal", &["--"]),
+    ("xfce4-terminal", &["-x"]),
+    ("xterm", &["-e"]),
+];
+
+/// Returns the command prefix used to run programs inside a terminal, taken
+/// from the `terminal` preference, split like a shell would, or the first
+/// known emulator found on `$PATH`.
+pub fn resolve_terminal(preference: Option<&str>) -> Option<Vec<String>> {
+    if let Some(preference) = preference {
+        match shlex::split(preference) {
+            Some(command) if !command.is_empty() => return Some(command),
+            Some(_) => {}
+            None => {
+                tracing::warn!(preference, "Unbalanced quotes in the terminal preference.");
+            }
+        }
+    }
+
+    let detected = KNOWN_TERMINALS
+        .iter()
+        .find(|(binary, _)| find_in_path(binary).is_some())
+        .map(|(binary, args)| {
+            std::iter::once(*binary)
+                .chain(args.iter().copied())
+                .map(str::to_string)
+                .collect()
+        });
+
+    if detected.is_none() {
+        tracing::warn!("No terminal emulator found. Set `terminal` in your preferences.");
+    }
+
+    detected
+}
+
+pub fn wrap_in_terminal(terminal: &[String], command: Vec<String>) -> Vec<String> {
+    terminal.iter().cloned().chain(command).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminal_preference_keeps_quoted_arguments_together() {
+        let terminal = resolve_terminal(Some(r#"wezterm start --class "Lucien Run" --"#));
+        assert_eq!(
+            terminal.unwrap(),
+            ["wezterm", "start", "--class", "Lucien Run", "--"]
+        );
+    }
+}
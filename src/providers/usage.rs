@@ -0,0 +1,336 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use sqlx::{
+    SqlitePool,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+};
+
+use super::ProviderKind;
+
+/// Time after which a launch counts half as much as a launch made right now.
+const HALF_LIFE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Launches older than this weigh almost nothing and are deleted.
+const RETENTION: Duration = Duration::from_secs(180 * 24 * 60 * 60);
+/// Query associations not used for this long are forgotten.
+const ASSOCIATION_EXPIRY: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// What the launch history of a provider tells about its entries.
+#[derive(Debug, Clone, Default)]
+pub struct UsageHistory {
+    /// Every launch adds a weight that decays exponentially with its age.
+    frecency: HashMap<String, f64>,
+    /// The entry ID last launched for each query, and every prefix of it.
+    associations: HashMap<String, String>,
+}
+
+impl UsageHistory {
+    pub fn frecency(&self, id: &str) -> f64 {
+        self.frecency.get(id).copied().unwrap_or_default()
+    }
+
+    /// The entry learned for `query`, as typed in the prompt.
+    pub fn association(&self, query: &str) -> Option<&str> {
+        self.associations
+            .get(&normalize_query(query))
+            .map(String::as_str)
+    }
+
+    /// Accounts for a launch until the history is loaded again.
+    pub fn record(&mut self, id: &str, query: &str) {
+        *self.frecency.entry(id.to_string()).or_default() += 1.0;
+
+        let query = normalize_query(query);
+        for prefix in query_prefixes(&query) {
+            self.associations.insert(prefix.to_string(), id.to_string());
+        }
+    }
+
+    /// Removes the association of `query`, returning whether there was one.
+    pub fn forget(&mut self, query: &str) -> bool {
+        self.associations.remove(&normalize_query(query)).is_some()
+    }
+}
+
+/// Records every launch, and the query it was launched from, per provider,
+/// in `$XDG_DATA_HOME/lucien/usage.db`.
+#[derive(Debug, Clone)]
+pub struct UsageStore {
+    pool: SqlitePool,
+}
+
+impl UsageStore {
+    pub async fn open() -> anyhow::Result<Self> {
+        let usage_file = xdg::BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"))
+            .place_data_file("usage.db")?;
+        let conn_options = SqliteConnectOptions::new()
+            .filename(&usage_file)
+            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
+            .synchronous(sqlx::sqlite::SqliteSynchronous::Normal)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(2)
+            .connect_with(conn_options)
+            .await?;
+
+        Self::from_pool(pool).await
+    }
+
+    async fn from_pool(pool: SqlitePool) -> anyhow::Result<Self> {
+        migrate(&pool).await?;
+        sqlx::query("DELETE FROM launches WHERE launched_at < ? OR provider = ?")
+            .bind(now() - RETENTION.as_secs() as i64)
+            .bind(ProviderKind::Clipboard.to_string())
+            .execute(&pool)
+            .await?;
+        sqlx::query("DELETE FROM associations WHERE used_at < ? OR provider = ?")
+            .bind(now() - ASSOCIATION_EXPIRY.as_secs() as i64)
+            .bind(ProviderKind::Clipboard.to_string())
+            .execute(&pool)
+            .await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Records a launch of `id`. A non-empty `query`, and every prefix of it, is
+    /// associated with the entry, replacing whatever was launched from them
+    /// before. Nothing is recorded for
+    /// providers whose entry IDs must not be written to disk.
+    pub async fn record(self, provider: ProviderKind, id: String, query: String) {
+        if !provider.records_usage() {
+            return;
+        }
+
+        let launched_at = now();
+        let result =
+            sqlx::query("INSERT INTO launches (provider, entry_id, launched_at) VALUES (?, ?, ?)")
+                .bind(provider.to_string())
+                .bind(&id)
+                .bind(launched_at)
+                .execute(&self.pool)
+                .await;
+
+        if let Err(e) = result {
+            tracing::warn!(error = %e, id, "Failed to record launch");
+        }
+
+        let query = normalize_query(&query);
+        if query.is_empty() {
+            return;
+        }
+
+        let result = async {
+            let mut transaction = self.pool.begin().await?;
+            for prefix in query_prefixes(&query) {
+                sqlx::query(
+                    "INSERT OR REPLACE INTO associations (provider, query, entry_id, used_at) \
+                    VALUES (?, ?, ?, ?)",
+                )
+                .bind(provider.to_string())
+                .bind(prefix)
+                .bind(&id)
+                .bind(launched_at)
+                .execute(&mut *transaction)
+                .await?;
+            }
+            transaction.commit().await
+        }
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!(error = %e, id, query, "Failed to record query association");
+        }
+    }
+
+    pub async fn forget(self, provider: ProviderKind, query: String) {
+        let result = sqlx::query("DELETE FROM associations WHERE provider = ? AND query = ?")
+            .bind(provider.to_string())
+            .bind(normalize_query(&query))
+            .execute(&self.pool)
+            .await;
+
+        if let Err(e) = result {
+            tracing::warn!(error = %e, query, "Failed to forget query association");
+        }
+    }
+
+    pub async fn history(self, provider: ProviderKind) -> UsageHistory {
+        let launches = sqlx::query_as::<_, (String, i64)>(
+            "SELECT entry_id, launched_at FROM launches WHERE provider = ?",
+        )
+        .bind(provider.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .inspect_err(|e| tracing::warn!(error = %e, "Failed to read launch history"))
+        .unwrap_or_default();
+
+        let now = now();
+        let decay_rate = std::f64::consts::LN_2 / HALF_LIFE.as_secs_f64();
+        let mut frecency = HashMap::new();
+
+        for (id, launched_at) in launches {
+            let age = now.saturating_sub(launched_at).max(0) as f64;
+            *frecency.entry(id).or_default() += (-decay_rate * age).exp();
+        }
+
+        let associations = sqlx::query_as::<_, (String, String)>(
+            "SELECT query, entry_id FROM associations WHERE provider = ? AND used_at >= ?",
+        )
+        .bind(provider.to_string())
+        .bind(now - ASSOCIATION_EXPIRY.as_secs() as i64)
+        .fetch_all(&self.pool)
+        .await
+        .inspect_err(|e| tracing::warn!(error = %e, "Failed to read query associations"))
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+        UsageHistory {
+            frecency,
+            associations,
+        }
+    }
+}
+
+/// Queries are remembered regardless of case and surrounding whitespace.
+fn normalize_query(query: &str) -> String {
+    query.trim().to_lowercase()
+}
+
+/// The prefixes of a normalized query that the prompt can be trimmed to,
+/// from the first character up to the whole query.
+fn query_prefixes(query: &str) -> impl Iterator<Item = &str> {
+    query
+        .char_indices()
+        .map(|(i, c)| &query[..i + c.len_utf8()])
+        .filter(|prefix| !prefix.ends_with(char::is_whitespace))
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs() as i64)
+}
+
+async fn migrate(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS launches (
+            provider TEXT NOT NULL,
+            entry_id TEXT NOT NULL,
+            launched_at INTEGER NOT NULL
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS launches_by_entry ON launches (provider, entry_id);")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS associations (
+            provider TEXT NOT NULL,
+            query TEXT NOT NULL,
+            entry_id TEXT NOT NULL,
+            used_at INTEGER NOT NULL,
+            PRIMARY KEY (provider, query)
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    async fn memory_store() -> UsageStore {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        UsageStore::from_pool(pool).await.unwrap()
+    }
+
+    async fn row_count(store: &UsageStore, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(&store.pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn clipboard_launches_are_not_persisted() {
+        block_on(async {
+            let store = memory_store().await;
+            let secret = "correct horse battery staple".to_string();
+            store
+                .clone()
+                .record(ProviderKind::Clipboard, secret, "cor".to_string())
+                .await;
+
+            assert_eq!(row_count(&store, "launches").await, 0);
+            assert_eq!(row_count(&store, "associations").await, 0);
+
+            store
+                .clone()
+                .record(
+                    ProviderKind::App,
+                    "firefox.desktop".to_string(),
+                    "fi".to_string(),
+                )
+                .await;
+            assert_eq!(row_count(&store, "launches").await, 1);
+        });
+    }
+
+    #[test]
+    fn shorter_prefix_of_a_query_finds_the_launched_entry() {
+        block_on(async {
+            let store = memory_store().await;
+            store
+                .clone()
+                .record(
+                    ProviderKind::App,
+                    "firefox.desktop".to_string(),
+                    "Fire".to_string(),
+                )
+                .await;
+            store
+                .clone()
+                .record(
+                    ProviderKind::App,
+                    "files.desktop".to_string(),
+                    "fil".to_string(),
+                )
+                .await;
+
+            let history = store.history(ProviderKind::App).await;
+            assert_eq!(history.association("fir"), Some("firefox.desktop"));
+            assert_eq!(history.association("fire"), Some("firefox.desktop"));
+            assert_eq!(history.association("f"), Some("files.desktop"));
+            assert_eq!(history.association("fires"), None);
+        });
+    }
+
+    #[test]
+    fn query_prefixes_skip_trailing_whitespace() {
+        let prefixes: Vec<_> = query_prefixes("vs c").collect();
+        assert_eq!(prefixes, ["v", "vs", "vs c"]);
+    }
+}
@@ -1,4 +1,8 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
 use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
 use iced::{
@@ -14,6 +18,7 @@ use crate::{
         keybindings::Action,
         theme::{ButtonClass, CustomTheme, Entry as EntryStyle, TextClass},
     },
+    providers::usage::UsageHistory,
     ui::icon::{ENTER, ICON_PLACEHOLDER, STAR_ACTIVE, STAR_INACTIVE},
 };
 
@@ -41,7 +46,7 @@ pub enum EntryIcon {
 }
 
 impl EntryIcon {
-    pub fn into_bytes(&self) -> Option<Vec<u8>> {
+    pub fn to_bytes(&self) -> Option<Vec<u8>> {
         match self {
             EntryIcon::Lazy(id) => {
                 let mut v = vec![0x00];
@@ -89,7 +94,7 @@ impl<'q> sqlx::Encode<'q, sqlx::Sqlite> for EntryIcon {
         &self,
         buf: &mut <sqlx::Sqlite as sqlx::Database>::ArgumentBuffer<'q>,
     ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
-        match self.into_bytes() {
+        match self.to_bytes() {
             Some(bytes) => <Vec<u8> as sqlx::Encode<sqlx::Sqlite>>::encode_by_ref(&bytes, buf),
             None => Ok(sqlx::encode::IsNull::Yes),
         }
@@ -102,8 +107,40 @@ pub struct Entry {
     pub main: String,
     pub secondary: Option<String>,
     pub icon: EntryIcon,
+    #[sqlx(skip)]
+    pub command: Vec<String>,
+    #[sqlx(skip)]
+    pub search_terms: SearchTerms,
+    #[sqlx(skip)]
+    pub working_dir: Option<PathBuf>,
+    /// Variables added to the environment of the launched process.
+    #[sqlx(skip)]
+    pub env: Vec<(String, String)>,
+}
+
+/// Secondary fields matched against the query. Each one ranks below `Entry::main`
+/// and the fields declared before it.
+#[derive(Debug, Clone, Default)]
+pub struct SearchTerms {
+    pub generic_name: Option<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
 }
 
+/// The most important field a query matched, which entries are ranked by
+/// before their score. Declared from least to most important.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MatchedField {
+    Category,
+    Keyword,
+    GenericName,
+    Main,
+}
+
+/// How strongly frecency multiplies the match score, on a logarithmic scale
+/// so a few heavy favorites cannot bury better matches.
+const FRECENCY_BOOST: f64 = 0.5;
+
 impl Entry {
     pub fn new(
         id: impl Into<String>,
@@ -116,8 +153,58 @@ impl Entry {
             main: main.into(),
             secondary: secondary.map(Into::into),
             icon,
+            command: Vec::new(),
+            search_terms: SearchTerms::default(),
+            working_dir: None,
+            env: Vec::new(),
         }
     }
+
+    pub fn command(mut self, command: Vec<String>) -> Self {
+        self.command = command;
+        self
+    }
+
+    pub fn search_terms(mut self, search_terms: SearchTerms) -> Self {
+        self.search_terms = search_terms;
+        self
+    }
+
+    pub fn working_dir(mut self, working_dir: Option<PathBuf>) -> Self {
+        self.working_dir = working_dir;
+        self
+    }
+
+    pub fn env(mut self, env: Vec<(String, String)>) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// The most important field matching `pattern`, with its fuzzy score.
+    fn match_score(&self, matcher: &SkimMatcherV2, pattern: &str) -> Option<(MatchedField, i64)> {
+        let best_match = |fields: &[String]| {
+            fields
+                .iter()
+                .filter_map(|field| matcher.fuzzy_match(field, pattern))
+                .max()
+        };
+        let terms = &self.search_terms;
+
+        let main = matcher.fuzzy_match(&self.main, pattern);
+        let generic_name = terms
+            .generic_name
+            .as_deref()
+            .and_then(|generic_name| matcher.fuzzy_match(generic_name, pattern));
+
+        [
+            (MatchedField::Main, main),
+            (MatchedField::GenericName, generic_name),
+            (MatchedField::Keyword, best_match(&terms.keywords)),
+            (MatchedField::Category, best_match(&terms.categories)),
+        ]
+        .into_iter()
+        .find_map(|(field, score)| Some((field, score?)))
+    }
 }
 
 pub fn display_entry<'a>(
@@ -240,7 +327,6 @@ impl EntryRegistry {
         self.registry.clear();
     }
 
-    #[allow(dead_code)]
     pub fn push(&mut self, entry: Entry) {
         let id = entry.id.clone();
         let index = self.entries.len();
@@ -263,6 +349,54 @@ impl EntryRegistry {
         }
     }
 
+    /// Replaces the entry sharing the ID of `entry`, or appends it.
+    pub fn upsert(&mut self, entry: Entry) {
+        match self.registry.get(&entry.id) {
+            Some(&index) => self.entries[index] = entry,
+            None => self.push(entry),
+        }
+    }
+
+    pub fn remove(&mut self, ids: &[String]) {
+        let removed: HashSet<usize> = ids
+            .iter()
+            .filter_map(|id| self.registry.remove(id))
+            .collect();
+
+        if removed.is_empty() {
+            return;
+        }
+
+        // Maps each surviving index to its position once the removed entries are gone.
+        let mut new_indices = Vec::with_capacity(self.entries.len());
+        let mut next_index = 0;
+        for index in 0..self.entries.len() {
+            if removed.contains(&index) {
+                new_indices.push(None);
+            } else {
+                new_indices.push(Some(next_index));
+                next_index += 1;
+            }
+        }
+
+        let mut index = 0;
+        self.entries.retain(|_| {
+            let keep = !removed.contains(&index);
+            index += 1;
+            keep
+        });
+        self.projection = self
+            .projection
+            .iter()
+            .filter_map(|&index| new_indices[index])
+            .collect();
+        for index in self.registry.values_mut() {
+            if let Some(new_index) = new_indices[*index] {
+                *index = new_index;
+            }
+        }
+    }
+
     pub fn get_visible_by_index(&self, visual_index: usize) -> Option<&Entry> {
         let &original_index = self.projection.get(visual_index)?;
         self.entries.get(original_index)
@@ -314,38 +448,108 @@ impl EntryRegistry {
         self.projection.iter().map(|&index| &self.entries[index])
     }
 
+    /// Orders the entries matching `pattern`: the entry last launched for that exact
+    /// query first, then favorites, then by match score boosted by frecency.
+    /// With an empty pattern, the frecency alone decides. Only hidden entries are
+    /// ranked when `show_hidden` is set, and none of them otherwise.
     pub fn sort_by_rank(
         &mut self,
         preferences: &Preferences,
         matcher: &SkimMatcherV2,
         pattern: &str,
+        usage_history: &UsageHistory,
+        show_hidden: bool,
     ) {
-        let mut ranked: Vec<(i64, usize)> = self
+        let learned_id = usage_history.association(pattern);
+        let mut ranked: Vec<(MatchedField, f64, f64, usize)> = self
             .entries
             .iter()
             .enumerate()
+            .filter(|(_, entry)| {
+                preferences.hidden_entries.matches(&entry.id, &entry.main) == show_hidden
+            })
             .filter_map(|(index, entry)| {
-                let score = matcher.fuzzy_match(&entry.main, pattern)?;
-                Some((score, index))
+                let (field, score) = entry.match_score(matcher, pattern)?;
+                let frecency = usage_history.frecency(&entry.id);
+                let boosted_score = score as f64 * (1.0 + FRECENCY_BOOST * frecency.ln_1p());
+                Some((field, boosted_score, frecency, index))
             })
             .collect();
 
-        ranked.sort_by(|(score_a, index_a), (score_b, index_b)| {
-            let entry_a = &self.entries[*index_a];
-            let entry_b = &self.entries[*index_b];
-            let a_is_fav = preferences.favorite_apps.contains(&entry_a.id);
-            let b_is_fav = preferences.favorite_apps.contains(&entry_b.id);
-
-            match (a_is_fav, b_is_fav) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => score_b.cmp(score_a),
-            }
-        });
+        ranked.sort_by(
+            |(field_a, score_a, frecency_a, index_a), (field_b, score_b, frecency_b, index_b)| {
+                let entry_a = &self.entries[*index_a];
+                let entry_b = &self.entries[*index_b];
+                let a_is_learned = learned_id == Some(entry_a.id.as_str());
+                let b_is_learned = learned_id == Some(entry_b.id.as_str());
+                let a_is_fav = preferences.favorite_apps.contains(&entry_a.id);
+                let b_is_fav = preferences.favorite_apps.contains(&entry_b.id);
+
+                b_is_learned
+                    .cmp(&a_is_learned)
+                    .then_with(|| b_is_fav.cmp(&a_is_fav))
+                    .then_with(|| field_b.cmp(field_a))
+                    .then_with(|| score_b.total_cmp(score_a))
+                    .then_with(|| frecency_b.total_cmp(frecency_a))
+            },
+        );
 
         self.projection = ranked
             .into_iter()
-            .map(|(_score, app_index)| app_index)
+            .map(|(_field, _score, _frecency, app_index)| app_index)
             .collect();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, main: &str) -> Entry {
+        Entry::new(id, main, None::<String>, EntryIcon::Lazy(id.to_string()))
+    }
+
+    fn ranked_ids(registry: &mut EntryRegistry, pattern: &str) -> Vec<String> {
+        registry.sort_by_rank(
+            &Preferences::default(),
+            &SkimMatcherV2::default(),
+            pattern,
+            &UsageHistory::default(),
+            false,
+        );
+        registry.iter_visible().map(|e| e.id.clone()).collect()
+    }
+
+    #[test]
+    fn score_breaks_ties_within_a_field() {
+        let mut registry = EntryRegistry::default();
+        registry.extend([
+            entry("weak", "Fancy Info Reader"),
+            entry("strong", "Firefox"),
+        ]);
+
+        assert_eq!(ranked_ids(&mut registry, "fire"), ["strong", "weak"]);
+    }
+
+    #[test]
+    fn remove_keeps_the_remaining_entries_reachable() {
+        let mut registry = EntryRegistry::default();
+        registry.extend(["a", "b", "c", "d"].map(|id| entry(id, id)));
+        ranked_ids(&mut registry, "");
+
+        registry.remove(&["b".to_string(), "missing".to_string(), "d".to_string()]);
+
+        assert_eq!(registry.len(), 2);
+        assert!(registry.get_by_id("b").is_none());
+        assert_eq!(registry.get_by_id("c").map(|e| e.main.as_str()), Some("c"));
+        let mut visible: Vec<&str> = registry.iter_visible().map(|e| e.id.as_str()).collect();
+        visible.sort();
+        assert_eq!(visible, ["a", "c"]);
+
+        registry.upsert(entry("c", "C"));
+        registry.upsert(entry("e", "e"));
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.get_by_id("c").map(|e| e.main.as_str()), Some("C"));
+        assert_eq!(registry.get_by_id("e").map(|e| e.id.as_str()), Some("e"));
+    }
+}
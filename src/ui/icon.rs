@@ -2,10 +2,6 @@ use std::sync::LazyLock;
 
 use iced::widget::image;
 
-pub const ICON_SIZES: [&str; 7] = [
-    "512x512", "256x256", "128x128", "96x96", "64x64", "48x48", "32x32",
-];
-
 pub const ICON_EXTENSIONS: [&str; 2] = ["svg", "png"];
 
 macro_rules! bake_icon {
@@ -14,6 +10,9 @@ macro_rules! bake_icon {
     };
 }
 
+// Baked assets are kept at several times their display size (64px for UI icons,
+// 256px for mimetypes) so they are downscaled, never upscaled, on HiDPI outputs.
+
 // #EBECF2 - Active icons color
 // #808080 - Inactive icons color
 
@@ -39,16 +38,33 @@ pub static CLIPBOARD_ACTIVE: LazyLock<image::Handle> =
 pub static CLIPBOARD_INACTIVE: LazyLock<image::Handle> =
     bake_icon!("../../assets/icons/clipboard-inactive.png");
 
+pub static TERMINAL_ACTIVE: LazyLock<image::Handle> =
+    bake_icon!("../../assets/icons/terminal-active.png");
+pub static TERMINAL_INACTIVE: LazyLock<image::Handle> =
+    bake_icon!("../../assets/icons/terminal-inactive.png");
+
 pub static ICON_PLACEHOLDER: LazyLock<image::Handle> =
     bake_icon!("../../assets/icons/icon-placeholder.png");
 
 // --- Mimetypes / Generic Icons ---
 pub static APPLICATION_DEFAULT: LazyLock<image::Handle> =
     bake_icon!("../../assets/mimetypes/application-x-executable.png");
+pub static APPLICATION_GENERIC: LazyLock<image::Handle> =
+    bake_icon!("../../assets/mimetypes/application-x-generic.png");
+pub static SHAREDLIB_GENERIC: LazyLock<image::Handle> =
+    bake_icon!("../../assets/mimetypes/application-x-sharedlib.png");
+pub static ADDON_GENERIC: LazyLock<image::Handle> =
+    bake_icon!("../../assets/mimetypes/application-x-addon.png");
 pub static FOLDER_DEFAULT: LazyLock<image::Handle> =
     bake_icon!("../../assets/mimetypes/inode-directory.png");
+pub static SYMLINK_DEFAULT: LazyLock<image::Handle> =
+    bake_icon!("../../assets/mimetypes/inode-symlink.png");
 pub static TEXT_GENERIC: LazyLock<image::Handle> =
     bake_icon!("../../assets/mimetypes/text-x-generic.png");
+pub static HTML_GENERIC: LazyLock<image::Handle> =
+    bake_icon!("../../assets/mimetypes/text-html.png");
+pub static SCRIPT_GENERIC: LazyLock<image::Handle> =
+    bake_icon!("../../assets/mimetypes/text-x-script.png");
 pub static IMAGE_GENERIC: LazyLock<image::Handle> =
     bake_icon!("../../assets/mimetypes/image-x-generic.png");
 pub static AUDIO_GENERIC: LazyLock<image::Handle> =
@@ -7,6 +7,7 @@ use crate::preferences::theme::CustomTheme;
 
 pub struct Prompt<'a, Message> {
     prompt: &'a str,
+    placeholder: &'a str,
     magnifier: Option<image::Handle>,
     style: &'a CustomTheme,
     on_input: Option<Box<dyn Fn(String) -> Message + 'a>>,
@@ -22,6 +23,7 @@ where
     pub fn new(prompt: &'a str, style: &'a CustomTheme) -> Self {
         Self {
             prompt,
+            placeholder: "Search...",
             style,
             magnifier: None,
             on_input: None,
@@ -46,6 +48,11 @@ where
         self
     }
 
+    pub fn placeholder(mut self, placeholder: &'a str) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
     pub fn magnifier(mut self, image: image::Handle) -> Self {
         self.magnifier = Some(image);
         self
@@ -68,7 +75,7 @@ where
                 .into(),
         };
 
-        let mut input = text_input("Search...", self.prompt)
+        let mut input = text_input(self.placeholder, self.prompt)
             .padding(12)
             .size(self.style.prompt.font_size as u32)
             .font(iced::Font {